@@ -11,23 +11,63 @@ use {
     super::resource::{
         BytecodeModule, BytecodeOptimizationLevel, DataLocation, ResourceData, SourceModule,
     },
-    anyhow::Result,
+    anyhow::{anyhow, Result},
+    globset::{Glob, GlobSet, GlobSetBuilder},
     itertools::Itertools,
     std::collections::{BTreeMap, HashSet},
     std::ffi::OsStr,
     std::path::{Path, PathBuf},
+    std::sync::Arc,
 };
 
 pub fn is_package_from_path(path: &Path) -> bool {
-    let file_name = path.file_name().unwrap().to_str().unwrap();
-    file_name.starts_with("__init__.")
+    path.file_name()
+        .map(|f| f.to_string_lossy().starts_with("__init__."))
+        .unwrap_or(false)
+}
+
+/// Whether any of `path`'s Unix executable permission bits (owner/group/other) are set.
+///
+/// Always `false` on non-Unix platforms, which don't expose this concept the same way.
+#[cfg(unix)]
+fn path_is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::metadata(path)
+        .map(|metadata| metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn path_is_executable(_path: &Path) -> bool {
+    false
+}
+
+/// Whether `file_name` looks like a shared library, including versioned Linux
+/// shared objects such as `libfoo.so.1.2.3`, which don't carry a `.so` extension
+/// by `Path::extension()`'s reckoning.
+fn is_shared_library_filename(file_name: &str) -> bool {
+    if file_name.ends_with(".so") || file_name.ends_with(".dylib") || file_name.ends_with(".dll") {
+        return true;
+    }
+
+    if let Some(idx) = file_name.find(".so.") {
+        let suffix = &file_name[idx + 4..];
+        return !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit() || c == '.');
+    }
+
+    false
 }
 
 pub fn walk_tree_files(path: &Path) -> Box<dyn Iterator<Item = walkdir::DirEntry>> {
     let res = walkdir::WalkDir::new(path).sort_by(|a, b| a.file_name().cmp(b.file_name()));
 
     let filtered = res.into_iter().filter_map(|entry| {
-        let entry = entry.expect("unable to get directory entry");
+        // A directory entry can fail to resolve, e.g. a broken symlink or a permission
+        // error encountered mid-walk. Skip it rather than aborting the whole walk.
+        // Such entries are not reported to the caller; `PythonResourceIterator`
+        // skips them the same way and doesn't record them in its `errors()` either.
+        let entry = entry.ok()?;
 
         let path = entry.path();
 
@@ -41,6 +81,70 @@ pub fn walk_tree_files(path: &Path) -> Box<dyn Iterator<Item = walkdir::DirEntry
     Box::new(filtered)
 }
 
+/// Gitignore-style glob rules for pruning paths during a scan.
+///
+/// Deny patterns are checked first and are the ones that matter for directories:
+/// a directory matching a deny pattern is never descended into, so large excluded
+/// trees (vendored dependencies, test data, build artifacts) are never walked, let
+/// alone classified. Allow patterns only constrain which *files* are kept; if none
+/// are registered, every file that isn't denied is kept.
+///
+/// Patterns are matched against the path relative to the scan root, so `*.so` and
+/// `vendor/**` behave the way they would in a `.gitignore` regardless of where the
+/// tree being scanned lives on disk.
+pub struct PathFilter {
+    allow: Option<GlobSet>,
+    deny: GlobSet,
+}
+
+impl PathFilter {
+    /// Build a filter from glob patterns. `allow` may be empty, in which case every
+    /// path not matched by `deny` is kept.
+    pub fn new(allow: &[&str], deny: &[&str]) -> Result<Self> {
+        fn compile(patterns: &[&str]) -> Result<GlobSet> {
+            let mut builder = GlobSetBuilder::new();
+            for pattern in patterns {
+                builder.add(Glob::new(pattern)?);
+            }
+            Ok(builder.build()?)
+        }
+
+        Ok(PathFilter {
+            allow: if allow.is_empty() {
+                None
+            } else {
+                Some(compile(allow)?)
+            },
+            deny: compile(deny)?,
+        })
+    }
+
+    fn is_denied(&self, relative_path: &Path) -> bool {
+        self.deny.is_match(relative_path)
+    }
+
+    fn is_allowed(&self, relative_path: &Path) -> bool {
+        self.allow
+            .as_ref()
+            .map(|allow| allow.is_match(relative_path))
+            .unwrap_or(true)
+    }
+}
+
+/// A problem encountered while classifying a single path during a scan.
+///
+/// Malformed entries (an un-stemmable name, a `.pyc` at an unexpected path depth, etc.)
+/// are recorded here and skipped rather than aborting the entire scan, so a single odd
+/// file doesn't prevent the rest of a tree from being scanned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanError {
+    /// The path that couldn't be classified.
+    pub path: PathBuf,
+
+    /// A human-readable description of the problem.
+    pub message: String,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct ResourceFile {
     /// Filesystem path of this resource.
@@ -50,6 +154,16 @@ pub struct ResourceFile {
     pub relative_path: PathBuf,
 }
 
+/// Describes the flavor of an installed distribution metadata directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistributionMetadataFlavor {
+    /// A `*.dist-info` directory, as produced by installing a wheel.
+    DistInfo,
+
+    /// A `*.egg-info` directory, as produced by installing a legacy egg.
+    EggInfo,
+}
+
 /// Represents a Python resource backed by the filesystem.
 ///
 /// TODO unify with PythonResource.
@@ -79,21 +193,94 @@ pub enum PythonFileResource {
     /// A non-module Python resource.
     Resource(ResourceData),
 
+    /// A standalone shared library that isn't itself a Python extension module.
+    ///
+    /// i.e. a `.so`/`.dylib`/`.dll` that doesn't match any suffix in the scan's
+    /// `PythonModuleSuffixes`. This is typically a native dependency an extension
+    /// module links against and is bundled alongside, rather than something
+    /// importable in its own right.
+    SharedLibrary {
+        /// Dotted path of the library relative to the scan root, e.g. `foo.libbar.so`.
+        name: String,
+
+        /// Dotted path of the directory containing the library, e.g. `foo`.
+        package: String,
+
+        /// Filesystem path of the library.
+        path: PathBuf,
+    },
+
+    /// A file under a `bin`/`Scripts`/`scripts` directory: a console-script wrapper
+    /// or other helper script a distribution installs alongside its packages.
+    PathExtension {
+        /// Dotted path of the script relative to the scan root.
+        name: String,
+
+        /// Dotted path of the directory containing the script, e.g. `bin`.
+        package: String,
+
+        /// Filesystem path of the script.
+        path: PathBuf,
+
+        /// Whether the file's Unix permission bits mark it executable.
+        ///
+        /// Always `false` on non-Unix platforms, which don't expose this bit.
+        is_executable: bool,
+    },
+
     /// Internal variant to track resources.
     ///
     /// Should not be encountered outside this module.
     ResourceFile(ResourceFile),
 
+    /// A file belonging to an installed distribution's `*.dist-info` or
+    /// `*.egg-info` metadata directory.
+    ///
+    /// e.g. `METADATA`, `PKG-INFO`, `RECORD`, or `top_level.txt`.
+    DistributionResource {
+        /// Name of the package the metadata directory describes.
+        package: String,
+
+        /// Version string of the package, if it could be parsed from the
+        /// directory name.
+        version: Option<String>,
+
+        /// Path of the file relative to the metadata directory.
+        ///
+        /// e.g. `METADATA` or `RECORD`.
+        name: String,
+
+        /// Which kind of metadata directory this file came from.
+        flavor: DistributionMetadataFlavor,
+
+        /// Location of the file's data.
+        path: DataLocation,
+    },
+
     /// A Python egg.
     ///
     /// i.e. a .egg file.
     EggFile { path: PathBuf },
 
+    /// A Python wheel.
+    ///
+    /// i.e. a .whl file.
+    WheelFile { path: PathBuf },
+
     /// A Python path extension file.
     ///
     /// i.e. a .pth file.
     PthFile { path: PathBuf },
 
+    /// A PEP 420 implicit namespace package.
+    ///
+    /// This is a directory with no `__init__.py` of its own that directly holds
+    /// no module or extension module, but has resource files in it or packages,
+    /// modules or resource files beneath it. A directory without `__init__.py`
+    /// that directly holds a module or extension module is not reported as a
+    /// namespace package.
+    NamespacePackage { name: String },
+
     /// Any other file.
     Other {
         package: String,
@@ -103,59 +290,393 @@ pub enum PythonFileResource {
     },
 }
 
+/// A zip-based archive format that can be scanned without extracting to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveFormat {
+    /// A legacy `.egg` file.
+    Egg,
+
+    /// A `.whl` file, as produced by `pip wheel` / `setup.py bdist_wheel`.
+    Wheel,
+}
+
+/// Rewrite a wheel archive member's path to the path it would occupy once installed.
+///
+/// Wheels may ship files under `<distribution>-<version>.data/<key>/` for files that
+/// `pip` installs somewhere other than alongside the package, per PEP 427:
+/// `purelib`/`platlib` members land directly in `site-packages`, and `scripts` members
+/// land in a `scripts` directory alongside it. Everything else in the archive is
+/// already laid out as it would appear in `site-packages`.
+///
+/// `.data/headers/` members (C headers installed alongside the interpreter) have no
+/// equivalent `PythonFileResource` variant and are left under their `.data` path rather
+/// than invented a new classification for.
+fn wheel_installed_path(archive_member: &str) -> String {
+    for marker in &[".data/purelib/", ".data/platlib/"] {
+        if let Some(idx) = archive_member.find(marker) {
+            return archive_member[idx + marker.len()..].to_string();
+        }
+    }
+
+    if let Some(idx) = archive_member.find(".data/scripts/") {
+        let rest = &archive_member[idx + ".data/scripts/".len()..];
+        return format!("scripts/{}", rest);
+    }
+
+    archive_member.to_string()
+}
+
+/// Determine the `BytecodeOptimizationLevel` encoded in a registered bytecode suffix.
+///
+/// Optimized bytecode suffixes carry an `.opt-1` or `.opt-2` marker (e.g.
+/// `.cpython-38.opt-1.pyc`); everything else is unoptimized (level zero).
+fn bytecode_suffix_optimization_level(suffix: &str) -> BytecodeOptimizationLevel {
+    if suffix.contains(".opt-2.") || suffix.ends_with(".opt-2") {
+        BytecodeOptimizationLevel::Two
+    } else if suffix.contains(".opt-1.") || suffix.ends_with(".opt-1") {
+        BytecodeOptimizationLevel::One
+    } else {
+        BytecodeOptimizationLevel::Zero
+    }
+}
+
+/// Determine the `DistributionMetadataFlavor` of a path component, if any.
+fn distribution_metadata_flavor(component: &str) -> Option<DistributionMetadataFlavor> {
+    if component.ends_with(".dist-info") {
+        Some(DistributionMetadataFlavor::DistInfo)
+    } else if component.ends_with(".egg-info") {
+        Some(DistributionMetadataFlavor::EggInfo)
+    } else {
+        None
+    }
+}
+
+/// Parse a `<name>-<version>.dist-info` or `<name>-<version>.egg-info` directory
+/// stem into its package name and version, if a version could be determined.
+fn parse_distribution_dir_stem(
+    component: &str,
+    flavor: DistributionMetadataFlavor,
+) -> (String, Option<String>) {
+    let suffix = match flavor {
+        DistributionMetadataFlavor::DistInfo => ".dist-info",
+        DistributionMetadataFlavor::EggInfo => ".egg-info",
+    };
+
+    let stem = &component[0..component.len() - suffix.len()];
+
+    match stem.rfind('-') {
+        Some(i) => (stem[0..i].to_string(), Some(stem[i + 1..].to_string())),
+        None => (stem.to_string(), None),
+    }
+}
+
+/// Parse the RFC822-style key/value headers of a `METADATA` or `PKG-INFO` file.
+///
+/// Lines of the form `Key: Value` are parsed into a map. The payload body
+/// (anything after the first blank line) is ignored, as are continuation
+/// lines, since callers of this module only care about simple scalar fields
+/// like `Name`, `Version`, and `License`.
+pub fn parse_python_package_metadata(data: &[u8]) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+
+    for line in String::from_utf8_lossy(data).lines() {
+        if line.is_empty() {
+            break;
+        }
+
+        // Continuation lines of a multi-line value start with whitespace.
+        if line.starts_with(char::is_whitespace) {
+            continue;
+        }
+
+        if let Some(i) = line.find(':') {
+            let key = line[0..i].trim().to_string();
+            let value = line[i + 1..].trim().to_string();
+            fields.insert(key, value);
+        }
+    }
+
+    fields
+}
+
+/// Parse the `top_level.txt` file from a distribution metadata directory.
+///
+/// This is simply a newline-delimited list of top-level package/module names
+/// owned by the distribution.
+pub fn parse_python_package_top_level(data: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// A single entry in a distribution's `RECORD` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PythonPackageRecordEntry {
+    /// Path of the installed file, relative to the installation root.
+    pub path: String,
+
+    /// The `<algorithm>=<base64>` hash of the file, if recorded.
+    pub hash: Option<String>,
+
+    /// Size of the file in bytes, if recorded.
+    pub size: Option<u64>,
+}
+
+/// Parse the CSV `RECORD` file from a `*.dist-info` directory.
+///
+/// Each line has the form `path,hash,size`, where `hash` and `size` may be
+/// empty (as is the case for the `RECORD` file's own entry).
+pub fn parse_python_package_record(data: &[u8]) -> Vec<PythonPackageRecordEntry> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|line| {
+            let fields = line.splitn(3, ',').collect::<Vec<_>>();
+
+            PythonPackageRecordEntry {
+                path: fields.first().unwrap_or(&"").to_string(),
+                hash: fields.get(1).filter(|s| !s.is_empty()).map(|s| s.to_string()),
+                size: fields
+                    .get(2)
+                    .filter(|s| !s.is_empty())
+                    .and_then(|s| s.parse::<u64>().ok()),
+            }
+        })
+        .collect()
+}
+
 pub struct PythonResourceIterator {
     root_path: PathBuf,
     suffixes: PythonModuleSuffixes,
     walkdir_result: Box<dyn Iterator<Item = walkdir::DirEntry>>,
     seen_packages: HashSet<String>,
     resources: Vec<ResourceFile>,
+
+    /// Whether the implicit PEP 420 namespace package post-walk pass has run.
+    namespace_packages_resolved: bool,
+
+    /// Implicit namespace packages discovered by the post-walk pass, pending emission.
+    namespace_packages: Vec<String>,
+
+    /// Whether `.egg` and `.whl` archives should be opened and scanned rather than
+    /// treated as opaque files.
+    scan_archives: bool,
+
+    /// Resources discovered inside scanned archives, pending emission.
+    archive_entries: Vec<PythonFileResource>,
+
+    /// Problems encountered while classifying entries, attributed back to their path.
+    errors: Vec<ScanError>,
 }
 
 impl PythonResourceIterator {
-    fn new(path: &Path, suffixes: &PythonModuleSuffixes) -> PythonResourceIterator {
-        let res = walkdir::WalkDir::new(path).sort_by(|a, b| a.file_name().cmp(b.file_name()));
+    /// Build the directory walk, pruning against `filter` (if any) as it goes.
+    ///
+    /// A directory matched by `filter`'s deny list is never descended into. A file
+    /// that is denied, or that isn't matched by a non-empty allow list, is dropped
+    /// from the walk entirely rather than being yielded for classification.
+    fn build_walk(
+        path: &Path,
+        filter: Option<Arc<PathFilter>>,
+    ) -> Box<dyn Iterator<Item = walkdir::DirEntry>> {
+        let root_path = path.to_path_buf();
+        let entry_filter = filter.clone();
+        let walk_root = root_path.clone();
+
+        let filtered = walkdir::WalkDir::new(path)
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+            .into_iter()
+            .filter_entry(move |entry| {
+                let relative_path = entry.path().strip_prefix(&walk_root).unwrap_or(entry.path());
+
+                entry_filter
+                    .as_ref()
+                    .map(|filter| !filter.is_denied(relative_path))
+                    .unwrap_or(true)
+            })
+            .filter_map(move |entry| {
+                // As in `walk_tree_files`, a directory entry that can't be resolved (broken
+                // symlink, permission error, ...) is skipped rather than aborting the walk.
+                let entry = entry.ok()?;
 
-        let filtered = res.into_iter().filter_map(|entry| {
-            let entry = entry.expect("unable to get directory entry");
+                let path = entry.path();
 
-            let path = entry.path();
+                if path.is_dir() {
+                    return None;
+                }
+
+                if let Some(filter) = &filter {
+                    let relative_path = path.strip_prefix(&root_path).unwrap_or(path);
+
+                    if !filter.is_allowed(relative_path) {
+                        return None;
+                    }
+                }
 
-            if path.is_dir() {
-                None
-            } else {
                 Some(entry)
-            }
-        });
+            });
+
+        Box::new(filtered)
+    }
 
+    fn new(path: &Path, suffixes: &PythonModuleSuffixes) -> PythonResourceIterator {
         PythonResourceIterator {
             root_path: path.to_path_buf(),
             suffixes: suffixes.clone(),
-            walkdir_result: Box::new(filtered),
+            walkdir_result: Self::build_walk(path, None),
             seen_packages: HashSet::new(),
             resources: Vec::new(),
+            namespace_packages_resolved: false,
+            namespace_packages: Vec::new(),
+            scan_archives: false,
+            archive_entries: Vec::new(),
+            errors: Vec::new(),
         }
     }
 
-    fn resolve_dir_entry(&mut self, entry: walkdir::DirEntry) -> Option<PythonFileResource> {
-        let path = entry.path();
+    /// Prune the walk using gitignore-style glob rules, evaluated against each
+    /// path relative to the scan root.
+    ///
+    /// This only governs the filesystem walk: resources discovered inside an
+    /// archive (see [`Self::with_scan_archives`]) are not filtered, since the
+    /// archive itself is what the filter sees on disk.
+    pub fn with_filter(mut self, filter: PathFilter) -> Self {
+        self.walkdir_result = Self::build_walk(&self.root_path, Some(Arc::new(filter)));
+        self
+    }
+
+    /// Problems encountered while classifying entries so far.
+    ///
+    /// The scan keeps going past a malformed entry rather than aborting; this surfaces
+    /// what was skipped and why so callers can decide whether it matters to them.
+    pub fn errors(&self) -> &[ScanError] {
+        &self.errors
+    }
+
+    /// Record a non-fatal problem encountered while classifying `path`.
+    fn record_error(&mut self, path: &Path, message: impl Into<String>) {
+        self.errors.push(ScanError {
+            path: path.to_path_buf(),
+            message: message.into(),
+        });
+    }
+
+    /// Convert a path's components to owned strings.
+    ///
+    /// Components with non-UTF-8 bytes are lossily converted (rather than panicking),
+    /// with the substitution recorded as a warning against `original_path`.
+    fn path_components_lossy(&mut self, rel_path: &Path, original_path: &Path) -> Vec<String> {
+        let mut any_lossy = false;
+
+        let components = rel_path
+            .iter()
+            .map(|p| match p.to_str() {
+                Some(s) => s.to_string(),
+                None => {
+                    any_lossy = true;
+                    p.to_string_lossy().into_owned()
+                }
+            })
+            .collect();
+
+        if any_lossy {
+            self.record_error(
+                original_path,
+                "path contains non-UTF-8 components; used lossy conversion",
+            );
+        }
+
+        components
+    }
+
+    /// Enable scanning inside `.egg` and `.whl` archives instead of treating them as
+    /// opaque files.
+    ///
+    /// When enabled, archive members are classified using the same rules as on-disk
+    /// files, except their `DataLocation` points at the decompressed entry bytes rather
+    /// than a filesystem path, so a distribution can be enumerated without first
+    /// unpacking it.
+    pub fn with_scan_archives(mut self, enabled: bool) -> Self {
+        self.scan_archives = enabled;
+        self
+    }
+
+    /// Register every ancestor of each seen package as a seen package, along
+    /// with every directory that holds a buffered resource file.
+    ///
+    /// Each seen package is a dotted package name known to directly hold a Python
+    /// resource. Any ancestor of it that doesn't already hold a resource of
+    /// its own is a PEP 420 implicit namespace package: a directory with no
+    /// `__init__.py` that nonetheless needs to resolve as a package so
+    /// resources nested beneath it can be addressed.
+    fn resolve_namespace_packages(&mut self) {
+        let packages = self.seen_packages.clone();
+
+        for package in &packages {
+            let mut parts = package.split('.').collect::<Vec<_>>();
+            parts.pop();
+
+            while !parts.is_empty() {
+                let candidate = itertools::join(&parts, ".");
+
+                if self.seen_packages.insert(candidate.clone()) {
+                    self.namespace_packages.push(candidate);
+                }
+
+                parts.pop();
+            }
+        }
 
-        let mut rel_path = path
-            .strip_prefix(&self.root_path)
-            .expect("unable to strip path prefix");
-        let mut rel_str = rel_path.to_str().expect("could not convert path to str");
-        let mut components = rel_path
+        // A directory whose subtree holds only resource files -- no `.py`, bytecode, or
+        // extension module anywhere under it -- never populates `seen_packages` via the
+        // arms above, so without this it (and any of its own ancestors) would never
+        // become addressable as a namespace package, leaving those resources permanently
+        // unaddressable below. Unlike the loop above, this one doesn't pop the starting
+        // directory off first: a resource's immediate containing directory needs to be
+        // considered too, since nothing else would ever register it.
+        let resource_dirs = self
+            .resources
             .iter()
-            .map(|p| p.to_str().expect("unable to get path as str"))
+            .filter_map(|resource| resource.relative_path.parent())
+            .map(|parent| {
+                parent
+                    .iter()
+                    .map(|c| c.to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+            })
             .collect::<Vec<_>>();
 
-        // .dist-info directories contain packaging metadata. They aren't interesting to us.
-        // We /could/ emit these files if we wanted to. But until there is a need, exclude them.
-        if components[0].ends_with(".dist-info") {
-            return None;
+        for mut parts in resource_dirs {
+            while !parts.is_empty() {
+                let candidate = itertools::join(&parts, ".");
+
+                if self.seen_packages.insert(candidate.clone()) {
+                    self.namespace_packages.push(candidate);
+                }
+
+                parts.pop();
+            }
         }
 
-        // Ditto for .egg-info directories.
-        if components[0].ends_with(".egg-info") {
+        self.namespace_packages_resolved = true;
+    }
+
+    fn resolve_dir_entry(&mut self, entry: walkdir::DirEntry) -> Option<PythonFileResource> {
+        let path = entry.path();
+
+        let mut rel_path = match path.strip_prefix(&self.root_path) {
+            Ok(p) => p,
+            Err(_) => {
+                self.record_error(path, "unable to strip root path prefix");
+                return None;
+            }
+        };
+        let mut components = self.path_components_lossy(rel_path, path);
+
+        if components.is_empty() {
+            self.record_error(path, "path has no components");
             return None;
         }
 
@@ -163,15 +684,19 @@ impl PythonResourceIterator {
         // such.
         let in_site_packages = if components[0] == "site-packages" {
             let sp_path = self.root_path.join("site-packages");
-            rel_path = path
-                .strip_prefix(sp_path)
-                .expect("unable to strip site-packages prefix");
+            rel_path = match path.strip_prefix(&sp_path) {
+                Ok(p) => p,
+                Err(_) => {
+                    self.record_error(path, "unable to strip site-packages prefix");
+                    return None;
+                }
+            };
 
-            rel_str = rel_path.to_str().expect("could not convert path to str");
-            components = rel_path
-                .iter()
-                .map(|p| p.to_str().expect("unable to get path as str"))
-                .collect::<Vec<_>>();
+            components = self.path_components_lossy(rel_path, path);
+            if components.is_empty() {
+                self.record_error(path, "path has no components below site-packages");
+                return None;
+            }
 
             true
         } else {
@@ -181,7 +706,7 @@ impl PythonResourceIterator {
         // It looks like we're in an unpacked egg. This is similar to the site-packages
         // scenario: we essentially have a new package root that corresponds to the
         // egg's extraction directory.
-        if (&components[0..components.len() - 1])
+        if components[0..components.len() - 1]
             .iter()
             .any(|p| p.ends_with(".egg"))
         {
@@ -199,13 +724,18 @@ impl PythonResourceIterator {
                 }
             }
 
-            rel_path = path
-                .strip_prefix(egg_root_path)
-                .expect("unable to strip egg prefix");
-            components = rel_path
-                .iter()
-                .map(|p| p.to_str().expect("unable to get path as str"))
-                .collect::<Vec<_>>();
+            rel_path = match path.strip_prefix(&egg_root_path) {
+                Ok(p) => p,
+                Err(_) => {
+                    self.record_error(path, "unable to strip egg prefix");
+                    return None;
+                }
+            };
+            components = self.path_components_lossy(rel_path, path);
+            if components.is_empty() {
+                self.record_error(path, "path has no components below egg root");
+                return None;
+            }
 
             // Ignore EGG-INFO directory, as it is just packaging metadata.
             if components[0] == "EGG-INFO" {
@@ -213,16 +743,43 @@ impl PythonResourceIterator {
             }
         }
 
-        let file_name = rel_path.file_name().unwrap().to_string_lossy();
+        // .dist-info and .egg-info directories hold packaging metadata (name, version,
+        // license, the RECORD of installed files, etc). Surface them as distribution
+        // resources rather than silently dropping them so callers can map installed
+        // files back to the distribution that owns them. This has to run after the
+        // site-packages/egg root-stripping above, since those reassign `components`
+        // and a dist-info/egg-info directory normally lives under one of those roots
+        // rather than at the scan root.
+        if let Some(flavor) = distribution_metadata_flavor(&components[0]) {
+            let (package, version) = parse_distribution_dir_stem(&components[0], flavor);
+            let name = itertools::join(&components[1..], "/");
+
+            return Some(PythonFileResource::DistributionResource {
+                package,
+                version,
+                name,
+                flavor,
+                path: DataLocation::Path(path.to_path_buf()),
+            });
+        }
+
+        let file_name = match rel_path.file_name() {
+            Some(f) => f.to_string_lossy().into_owned(),
+            None => {
+                self.record_error(path, "path has no file name");
+                return None;
+            }
+        };
 
         for ext_suffix in &self.suffixes.extension {
-            if file_name.ends_with(ext_suffix) {
+            if file_name.ends_with(ext_suffix.as_str()) {
                 let package_parts = &components[0..components.len() - 1];
                 let mut package = itertools::join(package_parts, ".");
 
                 let module_name = &file_name[0..file_name.len() - ext_suffix.len()];
 
-                let mut full_module_name: Vec<&str> = package_parts.to_vec();
+                let mut full_module_name: Vec<&str> =
+                    package_parts.iter().map(String::as_str).collect();
 
                 let stem = if module_name == "__init__" {
                     "".to_string()
@@ -249,22 +806,39 @@ impl PythonResourceIterator {
             }
         }
 
-        // TODO use registered suffixes for source and bytecode detection.
+        // Only reached once every registered extension-module suffix above has failed
+        // to match, so this is a native library that isn't itself importable -- e.g. a
+        // dependency an extension module links against. Checked against the whole file
+        // name (not just `Path::extension()`) so versioned Linux libraries like
+        // `libfoo.so.1.2.3` are still recognized.
+        if is_shared_library_filename(&file_name) {
+            let package_parts = &components[0..components.len() - 1];
+
+            return Some(PythonFileResource::SharedLibrary {
+                name: itertools::join(&components, "."),
+                package: itertools::join(package_parts, "."),
+                path: path.to_path_buf(),
+            });
+        }
+
         let resource = match rel_path.extension().and_then(OsStr::to_str) {
             Some("py") => {
                 let package_parts = &components[0..components.len() - 1];
                 let mut package = itertools::join(package_parts, ".");
 
-                let module_name = rel_path
-                    .file_stem()
-                    .expect("unable to get file stem")
-                    .to_str()
-                    .expect("unable to convert path to str");
+                let module_name = match rel_path.file_stem() {
+                    Some(s) => s.to_string_lossy().into_owned(),
+                    None => {
+                        self.record_error(path, "unable to determine module name from file stem");
+                        return None;
+                    }
+                };
 
-                let mut full_module_name: Vec<&str> = package_parts.to_vec();
+                let mut full_module_name: Vec<&str> =
+                    package_parts.iter().map(String::as_str).collect();
 
                 if module_name != "__init__" {
-                    full_module_name.push(module_name);
+                    full_module_name.push(&module_name);
                 }
 
                 let full_module_name = itertools::join(full_module_name, ".");
@@ -278,44 +852,112 @@ impl PythonResourceIterator {
                 PythonFileResource::Source(SourceModule {
                     name: full_module_name,
                     source: DataLocation::Path(path.to_path_buf()),
-                    is_package: is_package_from_path(&path),
+                    is_package: is_package_from_path(path),
                 })
             }
             Some("pyc") => {
-                // .pyc files should be in a __pycache__ directory.
-                if components.len() < 2 {
-                    panic!("encountered .pyc file with invalid path: {}", rel_str);
-                }
-
-                // Possibly from Python 2?
-                if components[components.len() - 2] != "__pycache__" {
+                // Sourceless distributions ship a bare `<module>.pyc` next to no
+                // `.py` at all, outside of any `__pycache__` directory. It is the
+                // importable module itself, so treat it like a source module
+                // rather than shunting it off to `Other`.
+                if components.len() < 2 || components[components.len() - 2] != "__pycache__" {
                     let package_parts = &components[0..components.len() - 1];
-                    let package = itertools::join(package_parts, ".");
-                    let full_name = itertools::join(&components, ".");
-                    let stem = components[components.len() - 1].to_string();
-
-                    return Some(PythonFileResource::Other {
-                        package,
-                        stem,
-                        full_name,
-                        path: path.to_path_buf(),
-                    });
+                    let mut package = itertools::join(package_parts, ".");
+
+                    let module_name = match rel_path.file_stem() {
+                        Some(s) => s.to_string_lossy().into_owned(),
+                        None => {
+                            self.record_error(
+                                path,
+                                "unable to determine module name from file stem",
+                            );
+                            return None;
+                        }
+                    };
+
+                    let mut full_module_name: Vec<&str> =
+                        package_parts.iter().map(String::as_str).collect();
+
+                    if module_name != "__init__" {
+                        full_module_name.push(&module_name);
+                    }
+
+                    let full_module_name = itertools::join(full_module_name, ".");
+
+                    if package.is_empty() {
+                        package = full_module_name.clone();
+                    }
+
+                    self.seen_packages.insert(package.clone());
+
+                    return Some(PythonFileResource::Bytecode(BytecodeModule::from_path(
+                        &full_module_name,
+                        BytecodeOptimizationLevel::Zero,
+                        path,
+                    )));
                 }
 
                 let package_parts = &components[0..components.len() - 2];
                 let mut package = itertools::join(package_parts, ".");
 
-                // Files have format <package>/__pycache__/<module>.cpython-37.opt-1.pyc
-                let module_name = rel_path
-                    .file_stem()
-                    .expect("unable to get file stem")
-                    .to_str()
-                    .expect("unable to convert file stem to str");
-                let module_name_parts = module_name.split('.').collect_vec();
-                let module_name =
-                    itertools::join(&module_name_parts[0..module_name_parts.len() - 1], ".");
+                // Files have format <package>/__pycache__/<module><suffix>, where
+                // <suffix> is one of the registered bytecode suffixes, e.g.
+                // `.cpython-37.pyc` or `.cpython-37.opt-1.pyc`. Match against the
+                // registered suffixes (rather than hardcoded `.opt-N.pyc` string
+                // checks) so the optimization level and cache tag come from the
+                // interpreter's own configuration.
+                let matched_suffix = self
+                    .suffixes
+                    .bytecode
+                    .iter()
+                    .chain(self.suffixes.debug_bytecode.iter())
+                    .chain(self.suffixes.optimized_bytecode.iter())
+                    .filter(|suffix| file_name.ends_with(suffix.as_str()))
+                    .max_by_key(|suffix| suffix.len());
+
+                let (module_name, optimization_level) = match matched_suffix {
+                    Some(suffix) => (
+                        file_name[0..file_name.len() - suffix.len()].to_string(),
+                        bytecode_suffix_optimization_level(suffix),
+                    ),
+                    None => {
+                        // Suffix wasn't registered for this interpreter (e.g. scanning a
+                        // tree built for a different CPython build/ABI). Still surface it;
+                        // callers decide whether to keep or drop unrecognized cache tags.
+                        // Fall back to taking the first dotted component of the file
+                        // stem as the module name; the rest is the cache tag (and
+                        // optional opt-N marker).
+                        let stem = match rel_path.file_stem() {
+                            Some(s) => s.to_string_lossy().into_owned(),
+                            None => {
+                                self.record_error(
+                                    path,
+                                    "unable to determine cache tag from file stem",
+                                );
+                                return None;
+                            }
+                        };
+                        let parts = stem.split('.').collect_vec();
+                        let level = if stem.ends_with(".opt-1") {
+                            BytecodeOptimizationLevel::One
+                        } else if stem.ends_with(".opt-2") {
+                            BytecodeOptimizationLevel::Two
+                        } else {
+                            BytecodeOptimizationLevel::Zero
+                        };
+
+                        match parts.first() {
+                            Some(name) => (name.to_string(), level),
+                            None => {
+                                self.record_error(path, "bytecode file stem is empty");
+                                return None;
+                            }
+                        }
+                    }
+                };
 
-                let mut full_module_name: Vec<&str> = package_parts.to_vec();
+                let mut full_module_name: Vec<&str> =
+                    package_parts.iter().map(String::as_str).collect();
 
                 if module_name != "__init__" {
                     full_module_name.push(&module_name);
@@ -329,33 +971,53 @@ impl PythonResourceIterator {
 
                 self.seen_packages.insert(package.clone());
 
-                if rel_str.ends_with(".opt-1.pyc") {
-                    PythonFileResource::Bytecode(BytecodeModule::from_path(
-                        &full_module_name,
-                        BytecodeOptimizationLevel::One,
-                        path,
-                    ))
-                } else if rel_str.ends_with(".opt-2.pyc") {
-                    PythonFileResource::Bytecode(BytecodeModule::from_path(
-                        &full_module_name,
-                        BytecodeOptimizationLevel::Two,
-                        path,
-                    ))
-                } else {
-                    PythonFileResource::Bytecode(BytecodeModule::from_path(
-                        &full_module_name,
-                        BytecodeOptimizationLevel::Zero,
-                        path,
-                    ))
+                PythonFileResource::Bytecode(BytecodeModule::from_path(
+                    &full_module_name,
+                    optimization_level,
+                    path,
+                ))
+            }
+            Some("egg") => {
+                if self.scan_archives {
+                    let entries = self.scan_archive(path, ArchiveFormat::Egg);
+                    self.archive_entries.extend(entries);
+                    return None;
+                }
+
+                PythonFileResource::EggFile {
+                    path: path.to_path_buf(),
+                }
+            }
+            Some("whl") => {
+                if self.scan_archives {
+                    let entries = self.scan_archive(path, ArchiveFormat::Wheel);
+                    self.archive_entries.extend(entries);
+                    return None;
+                }
+
+                PythonFileResource::WheelFile {
+                    path: path.to_path_buf(),
                 }
             }
-            Some("egg") => PythonFileResource::EggFile {
-                path: path.to_path_buf(),
-            },
             Some("pth") => PythonFileResource::PthFile {
                 path: path.to_path_buf(),
             },
             _ => {
+                // Distributions commonly ship console-script wrappers under a
+                // `bin`/`Scripts`/`scripts` directory. Surface those distinctly rather
+                // than lumping them in with arbitrary package data.
+                if components[0] == "bin" || components[0] == "Scripts" || components[0] == "scripts"
+                {
+                    let package_parts = &components[0..components.len() - 1];
+
+                    return Some(PythonFileResource::PathExtension {
+                        name: itertools::join(&components, "."),
+                        package: itertools::join(package_parts, "."),
+                        is_executable: path_is_executable(path),
+                        path: path.to_path_buf(),
+                    });
+                }
+
                 // If it is some other file type, we categorize it as a resource
                 // file. The package name and resource name are resolved later,
                 // by the iterator.
@@ -368,25 +1030,287 @@ impl PythonResourceIterator {
 
         Some(resource)
     }
-}
 
-impl Iterator for PythonResourceIterator {
-    type Item = PythonFileResource;
+    /// Open a zip-based `.egg` or `.whl` archive and classify its members.
+    ///
+    /// This mirrors `resolve_dir_entry`'s classification rules, but reads each member's
+    /// bytes into memory rather than pointing at a path on disk, since the archive's
+    /// members don't exist as standalone files. Extension modules are intentionally not
+    /// specialized here: unlike `SourceModule` and `ResourceData`, `ExtensionModule`
+    /// only knows how to reference a filesystem path, so native libraries embedded in an
+    /// archive surface as plain resources instead. Likewise, shared libraries and
+    /// `bin`/`Scripts`/`scripts` members aren't specialized into `SharedLibrary` or
+    /// `PathExtension`; they surface as plain resources too.
+    ///
+    /// Invalid or unreadable archives are skipped rather than treated as a hard error,
+    /// consistent with how the rest of this scanner favors best-effort classification.
+    ///
+    /// Wheel layout is honored via `wheel_installed_path`'s member-path rewriting (the
+    /// same rules `pip` itself applies), not by reading `*.dist-info/RECORD`: `RECORD`
+    /// only carries the archive's own member paths plus hashes/sizes for install-time
+    /// verification, so it has nothing to add once a member's installed path is already
+    /// known from its location in the archive.
+    fn scan_archive(&mut self, path: &Path, format: ArchiveFormat) -> Vec<PythonFileResource> {
+        let mut entries = Vec::new();
+
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(_) => return entries,
+        };
 
-    fn next(&mut self) -> Option<PythonFileResource> {
-        // Our strategy is to walk directory entries and buffer resource files locally.
-        // We then emit those at the end, perhaps doing some post-processing along the
-        // way.
-        loop {
-            let res = self.walkdir_result.next();
+        let mut archive = match zip::ZipArchive::new(file) {
+            Ok(archive) => archive,
+            Err(_) => return entries,
+        };
 
-            // We're out of directory entries;
-            if res.is_none() {
-                break;
+        for i in 0..archive.len() {
+            let mut member = match archive.by_index(i) {
+                Ok(member) => member,
+                Err(_) => continue,
+            };
+
+            if member.is_dir() {
+                continue;
             }
 
-            let entry = res.unwrap();
-            let python_resource = self.resolve_dir_entry(entry);
+            let member_name = member.name().to_string();
+
+            let mut data = Vec::with_capacity(member.size() as usize);
+            if std::io::Read::read_to_end(&mut member, &mut data).is_err() {
+                continue;
+            }
+
+            // For wheels, files shipped under `<name>-<version>.data/{purelib,platlib}/`
+            // are installed straight into `site-packages` by pip rather than kept
+            // alongside the rest of the distribution. Reinterpret their path so they
+            // resolve the same way an on-disk `site-packages` scan would see them.
+            let installed_name = match format {
+                ArchiveFormat::Wheel => wheel_installed_path(&member_name),
+                ArchiveFormat::Egg => member_name.clone(),
+            };
+
+            let rel_path = PathBuf::from(&installed_name);
+            let components = installed_name
+                .split('/')
+                .filter(|c| !c.is_empty())
+                .collect::<Vec<_>>();
+
+            if components.is_empty() {
+                continue;
+            }
+
+            if let Some(flavor) = distribution_metadata_flavor(components[0]) {
+                let (package, version) = parse_distribution_dir_stem(components[0], flavor);
+                let name = itertools::join(&components[1..], "/");
+
+                entries.push(PythonFileResource::DistributionResource {
+                    package,
+                    version,
+                    name,
+                    flavor,
+                    path: DataLocation::Memory(data),
+                });
+                continue;
+            }
+
+            // EGG-INFO is the egg equivalent of a dist-info directory and carries no
+            // importable content.
+            if components[0] == "EGG-INFO" {
+                continue;
+            }
+
+            let file_name = rel_path
+                .file_name()
+                .map(|f| f.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            let package_parts = &components[0..components.len() - 1];
+            let mut package = itertools::join(package_parts, ".");
+
+            let resource = match rel_path.extension().and_then(OsStr::to_str) {
+                Some("py") => {
+                    let module_name = match rel_path.file_stem() {
+                        Some(s) => s.to_string_lossy().into_owned(),
+                        None => {
+                            self.record_error(
+                                path,
+                                "unable to determine module name from archive member's file stem",
+                            );
+                            continue;
+                        }
+                    };
+
+                    let mut full_module_name: Vec<&str> = package_parts.to_vec();
+
+                    if module_name != "__init__" {
+                        full_module_name.push(&module_name);
+                    }
+
+                    let full_module_name = itertools::join(full_module_name, ".");
+
+                    if package.is_empty() {
+                        package = full_module_name.clone();
+                    }
+
+                    self.seen_packages.insert(package.clone());
+
+                    PythonFileResource::Source(SourceModule {
+                        name: full_module_name,
+                        source: DataLocation::Memory(data),
+                        is_package: file_name.starts_with("__init__."),
+                    })
+                }
+                Some("pyc") if components.len() < 2
+                    || components[components.len() - 2] != "__pycache__" =>
+                {
+                    // Sourceless archive member: the module itself, same as
+                    // `resolve_dir_entry`'s bare-`.pyc` case.
+                    let module_name = match rel_path.file_stem() {
+                        Some(s) => s.to_string_lossy().into_owned(),
+                        None => {
+                            self.record_error(
+                                path,
+                                "unable to determine module name from archive member's file stem",
+                            );
+                            continue;
+                        }
+                    };
+
+                    let mut full_module_name: Vec<&str> = package_parts.to_vec();
+
+                    if module_name != "__init__" {
+                        full_module_name.push(&module_name);
+                    }
+
+                    let full_module_name = itertools::join(full_module_name, ".");
+
+                    if package.is_empty() {
+                        package = full_module_name.clone();
+                    }
+
+                    self.seen_packages.insert(package.clone());
+
+                    PythonFileResource::Bytecode(BytecodeModule {
+                        name: full_module_name,
+                        bytecode: DataLocation::Memory(data),
+                        optimization_level: BytecodeOptimizationLevel::Zero,
+                    })
+                }
+                Some("pyc") => {
+                    // Cached under `__pycache__`, same layout and cache-tag matching as
+                    // `resolve_dir_entry`'s on-disk equivalent.
+                    let package_parts = &components[0..components.len() - 2];
+                    let mut package = itertools::join(package_parts, ".");
+
+                    let matched_suffix = self
+                        .suffixes
+                        .bytecode
+                        .iter()
+                        .chain(self.suffixes.debug_bytecode.iter())
+                        .chain(self.suffixes.optimized_bytecode.iter())
+                        .filter(|suffix| file_name.ends_with(suffix.as_str()))
+                        .max_by_key(|suffix| suffix.len());
+
+                    let (module_name, optimization_level) = match matched_suffix {
+                        Some(suffix) => (
+                            file_name[0..file_name.len() - suffix.len()].to_string(),
+                            bytecode_suffix_optimization_level(suffix),
+                        ),
+                        None => {
+                            let stem = match rel_path.file_stem() {
+                                Some(s) => s.to_string_lossy().into_owned(),
+                                None => {
+                                    self.record_error(
+                                        path,
+                                        "unable to determine cache tag from file stem",
+                                    );
+                                    continue;
+                                }
+                            };
+                            let parts = stem.split('.').collect_vec();
+                            let level = if stem.ends_with(".opt-1") {
+                                BytecodeOptimizationLevel::One
+                            } else if stem.ends_with(".opt-2") {
+                                BytecodeOptimizationLevel::Two
+                            } else {
+                                BytecodeOptimizationLevel::Zero
+                            };
+
+                            match parts.first() {
+                                Some(name) => (name.to_string(), level),
+                                None => {
+                                    self.record_error(path, "bytecode file stem is empty");
+                                    continue;
+                                }
+                            }
+                        }
+                    };
+
+                    let mut full_module_name: Vec<&str> =
+                        package_parts.iter().map(String::as_str).collect();
+
+                    if module_name != "__init__" {
+                        full_module_name.push(&module_name);
+                    }
+
+                    let full_module_name = itertools::join(full_module_name, ".");
+
+                    if package.is_empty() {
+                        package = full_module_name.clone();
+                    }
+
+                    self.seen_packages.insert(package.clone());
+
+                    PythonFileResource::Bytecode(BytecodeModule {
+                        name: full_module_name,
+                        bytecode: DataLocation::Memory(data),
+                        optimization_level,
+                    })
+                }
+                _ => {
+                    // Non-module archive members are addressed relative to their
+                    // containing directory, same as loose resource files. Unlike the
+                    // on-disk post-walk pass, we don't defer until every member of the
+                    // archive has been seen: an archive's internal layout is self
+                    // contained, so the immediate parent directory is always the
+                    // owning package.
+                    if package.is_empty() {
+                        package = components[0].to_string();
+                    }
+
+                    PythonFileResource::Resource(ResourceData {
+                        full_name: installed_name.clone(),
+                        leaf_package: package,
+                        relative_name: file_name,
+                        data: DataLocation::Memory(data),
+                    })
+                }
+            };
+
+            entries.push(resource);
+        }
+
+        entries
+    }
+}
+
+impl Iterator for PythonResourceIterator {
+    type Item = PythonFileResource;
+
+    fn next(&mut self) -> Option<PythonFileResource> {
+        // Our strategy is to walk directory entries and buffer resource files locally.
+        // We then emit those at the end, perhaps doing some post-processing along the
+        // way.
+        loop {
+            let res = self.walkdir_result.next();
+
+            // We're out of directory entries;
+            if res.is_none() {
+                break;
+            }
+
+            let entry = res.unwrap();
+            let python_resource = self.resolve_dir_entry(entry);
 
             // Try the next directory entry.
             if python_resource.is_none() {
@@ -404,6 +1328,25 @@ impl Iterator for PythonResourceIterator {
             return Some(python_resource);
         }
 
+        // Drain resources discovered inside any scanned `.egg`/`.whl` archives before
+        // moving on to namespace package resolution, whose bookkeeping they've already
+        // fed via `seen_packages`.
+        if let Some(resource) = self.archive_entries.pop() {
+            return Some(resource);
+        }
+
+        // The filesystem walk is exhausted. Run the post-walk pass that registers
+        // implicit PEP 420 namespace packages so resources nested beneath them can
+        // resolve a leaf package below, then drain them before resources (their
+        // discovery determines which resources are even addressable).
+        if !self.namespace_packages_resolved {
+            self.resolve_namespace_packages();
+        }
+
+        if let Some(name) = self.namespace_packages.pop() {
+            return Some(PythonFileResource::NamespacePackage { name });
+        }
+
         loop {
             if self.resources.is_empty() {
                 return None;
@@ -432,12 +1375,15 @@ impl Iterator for PythonResourceIterator {
             // packages and can supplement the relative path (which is the one true resource
             // identifier) with annotations, such as the leaf-most Python package.
 
-            // Resources should always have a filename component. Otherwise how did we get here?
-            let basename = resource
-                .relative_path
-                .file_name()
-                .unwrap()
-                .to_string_lossy();
+            // Resources should always have a filename component, but don't take the whole
+            // scan down if a malformed entry somehow lacks one.
+            let basename = match resource.relative_path.file_name() {
+                Some(f) => f.to_string_lossy(),
+                None => {
+                    self.record_error(&resource.full_path, "resource path has no file name");
+                    continue;
+                }
+            };
 
             // The full name of the resource is its relative path with path separators normalized to
             // POSIX conventions.
@@ -503,20 +1449,25 @@ impl Iterator for PythonResourceIterator {
 /// can be addressed via the ``A.B.C`` naming convention.
 ///
 /// Returns an iterator of ``PythonResource`` instances.
+///
+/// `scan_archives` controls whether encountered `.egg`/`.whl` archives are opened and
+/// walked in place, rather than being emitted as opaque `EggFile`/`WheelFile` entries.
 pub fn find_python_resources(
     root_path: &Path,
     suffixes: &PythonModuleSuffixes,
+    scan_archives: bool,
 ) -> PythonResourceIterator {
-    PythonResourceIterator::new(root_path, suffixes)
+    PythonResourceIterator::new(root_path, suffixes).with_scan_archives(scan_archives)
 }
 
 pub fn find_python_modules(
     root_path: &Path,
     suffixes: &PythonModuleSuffixes,
+    scan_archives: bool,
 ) -> Result<BTreeMap<String, Vec<u8>>> {
     let mut mods = BTreeMap::new();
 
-    for resource in find_python_resources(root_path, suffixes) {
+    for resource in find_python_resources(root_path, suffixes, scan_archives) {
         if let PythonFileResource::Source(module) = resource {
             let data = module.source.resolve()?;
             mods.insert(module.name, data);
@@ -526,386 +1477,2156 @@ pub fn find_python_modules(
     Ok(mods)
 }
 
-#[cfg(test)]
-mod tests {
-    use {
-        super::*,
-        lazy_static::lazy_static,
-        std::fs::{create_dir_all, write},
-    };
+/// Output format for [`format_resource_record`] and [`find_resources_report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceRecordFormat {
+    /// One `key=value ...` line per resource, meant for a human to read or `grep`.
+    Text,
 
-    lazy_static! {
-        static ref EMPTY_SUFFIXES: PythonModuleSuffixes = PythonModuleSuffixes {
-            source: vec![],
-            bytecode: vec![],
-            debug_bytecode: vec![],
-            optimized_bytecode: vec![],
-            extension: vec![],
-        };
+    /// One JSON object per resource (JSON Lines), meant for diffing in CI or
+    /// feeding to other tooling.
+    Json,
+}
+
+/// The fields a `find-resources`-style report shows for every `PythonFileResource`,
+/// regardless of which variant produced it.
+struct ResourceRecordFields {
+    kind: &'static str,
+    full_name: String,
+    package: String,
+    path: String,
+    suffix: String,
+}
+
+fn data_location_display(location: &DataLocation) -> String {
+    match location {
+        DataLocation::Path(path) => path.display().to_string(),
+        DataLocation::Memory(data) => format!("<memory: {} bytes>", data.len()),
     }
+}
 
-    #[test]
-    fn test_source_resolution() {
-        let td = tempdir::TempDir::new("pyoxidizer-test").unwrap();
-        let tp = td.path();
+fn path_suffix(path: &Path) -> String {
+    path.extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default()
+}
 
-        let acme_path = tp.join("acme");
-        let acme_a_path = acme_path.join("a");
-        let acme_bar_path = acme_path.join("bar");
+/// Parent package of a dotted module name, e.g. `"foo.bar"` -> `"foo"`.
+fn parent_package(full_name: &str) -> String {
+    full_name.rsplit_once('.').map(|(pkg, _)| pkg.to_string()).unwrap_or_default()
+}
 
-        create_dir_all(&acme_a_path).unwrap();
-        create_dir_all(&acme_bar_path).unwrap();
+fn resource_record_fields(resource: &PythonFileResource) -> ResourceRecordFields {
+    match resource {
+        PythonFileResource::Source(module) => ResourceRecordFields {
+            kind: "source",
+            full_name: module.name.clone(),
+            package: parent_package(&module.name),
+            path: data_location_display(&module.source),
+            suffix: ".py".to_string(),
+        },
+        PythonFileResource::Bytecode(module) => ResourceRecordFields {
+            kind: "bytecode",
+            full_name: module.name.clone(),
+            package: parent_package(&module.name),
+            path: data_location_display(&module.bytecode),
+            suffix: format!("{:?}", module.optimization_level),
+        },
+        PythonFileResource::ExtensionModule {
+            package,
+            full_name,
+            path,
+            extension_file_suffix,
+            ..
+        } => ResourceRecordFields {
+            kind: "extension-module",
+            full_name: full_name.clone(),
+            package: package.clone(),
+            path: path.display().to_string(),
+            suffix: extension_file_suffix.clone(),
+        },
+        PythonFileResource::Resource(data) => ResourceRecordFields {
+            kind: "resource",
+            full_name: data.full_name.clone(),
+            package: data.leaf_package.clone(),
+            path: data_location_display(&data.data),
+            suffix: path_suffix(Path::new(&data.relative_name)),
+        },
+        PythonFileResource::ResourceFile(file) => ResourceRecordFields {
+            kind: "resource-file",
+            full_name: file.relative_path.display().to_string(),
+            package: String::new(),
+            path: file.full_path.display().to_string(),
+            suffix: path_suffix(&file.relative_path),
+        },
+        PythonFileResource::SharedLibrary {
+            name,
+            package,
+            path,
+        } => ResourceRecordFields {
+            kind: "shared-library",
+            full_name: name.clone(),
+            package: package.clone(),
+            path: path.display().to_string(),
+            suffix: path_suffix(path),
+        },
+        PythonFileResource::PathExtension {
+            name,
+            package,
+            path,
+            is_executable,
+        } => ResourceRecordFields {
+            kind: "path-extension",
+            full_name: name.clone(),
+            package: package.clone(),
+            path: path.display().to_string(),
+            suffix: match (path_suffix(path), *is_executable) {
+                (suffix, false) => suffix,
+                (suffix, true) if suffix.is_empty() => "executable".to_string(),
+                (suffix, true) => format!("{} executable", suffix),
+            },
+        },
+        PythonFileResource::DistributionResource {
+            package,
+            name,
+            path,
+            ..
+        } => ResourceRecordFields {
+            kind: "distribution-resource",
+            full_name: format!("{}/{}", package, name),
+            package: package.clone(),
+            path: data_location_display(path),
+            suffix: path_suffix(Path::new(name)),
+        },
+        PythonFileResource::EggFile { path } => ResourceRecordFields {
+            kind: "egg-file",
+            full_name: path.display().to_string(),
+            package: String::new(),
+            path: path.display().to_string(),
+            suffix: ".egg".to_string(),
+        },
+        PythonFileResource::WheelFile { path } => ResourceRecordFields {
+            kind: "wheel-file",
+            full_name: path.display().to_string(),
+            package: String::new(),
+            path: path.display().to_string(),
+            suffix: ".whl".to_string(),
+        },
+        PythonFileResource::PthFile { path } => ResourceRecordFields {
+            kind: "pth-file",
+            full_name: path.display().to_string(),
+            package: String::new(),
+            path: path.display().to_string(),
+            suffix: ".pth".to_string(),
+        },
+        PythonFileResource::NamespacePackage { name } => ResourceRecordFields {
+            kind: "namespace-package",
+            full_name: name.clone(),
+            package: name.clone(),
+            path: String::new(),
+            suffix: String::new(),
+        },
+        PythonFileResource::Other {
+            package,
+            full_name,
+            path,
+            ..
+        } => ResourceRecordFields {
+            kind: "other",
+            full_name: full_name.clone(),
+            package: package.clone(),
+            path: path.display().to_string(),
+            suffix: path_suffix(path),
+        },
+    }
+}
 
-        write(acme_path.join("__init__.py"), "").unwrap();
-        write(acme_a_path.join("__init__.py"), "").unwrap();
-        write(acme_bar_path.join("__init__.py"), "").unwrap();
+/// Escape a value for the `Text` format, so embedded whitespace can't be mistaken
+/// for a field separator.
+fn escape_text_field(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("{:?}", value)
+    } else {
+        value.to_string()
+    }
+}
 
-        write(acme_a_path.join("foo.py"), "# acme.foo").unwrap();
+/// Render `value` as a quoted JSON string.
+///
+/// Rust's `{:?}` Debug formatting looks like JSON escaping but isn't: it renders
+/// control bytes braced and variable-width (e.g. a BEL byte becomes `\u{7}`),
+/// which no JSON parser accepts. JSON requires a fixed 4-hex-digit escape
+/// with no braces, so fields are escaped by hand here instead.
+fn escape_json_field(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                escaped.push_str(&format!("\\u{:04x}", c as u32));
+            }
+            c => escaped.push(c),
+        }
+    }
 
-        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
-        assert_eq!(resources.len(), 4);
+    escaped.push('"');
+    escaped
+}
 
-        assert_eq!(
-            resources[0],
-            PythonFileResource::Source(SourceModule {
-                name: "acme".to_string(),
-                source: DataLocation::Path(acme_path.join("__init__.py")),
-                is_package: true,
-            })
-        );
-        assert_eq!(
-            resources[1],
-            PythonFileResource::Source(SourceModule {
-                name: "acme.a".to_string(),
-                source: DataLocation::Path(acme_a_path.join("__init__.py")),
-                is_package: true,
-            })
-        );
-        assert_eq!(
-            resources[2],
-            PythonFileResource::Source(SourceModule {
-                name: "acme.a.foo".to_string(),
-                source: DataLocation::Path(acme_a_path.join("foo.py")),
-                is_package: false,
-            })
-        );
-        assert_eq!(
-            resources[3],
-            PythonFileResource::Source(SourceModule {
-                name: "acme.bar".to_string(),
-                source: DataLocation::Path(acme_bar_path.join("__init__.py")),
-                is_package: true,
-            })
-        );
+/// Render one `PythonFileResource` as a single diagnostic line.
+///
+/// Used by `find-resources`-style triage tooling: when the scanner misclassifies
+/// something or drops a resource entirely, this dumps exactly what the iterator
+/// produced -- kind, full name, package, path, and suffix -- one stable, greppable
+/// record per line, without writing a throwaway program against the iterator.
+pub fn format_resource_record(resource: &PythonFileResource, format: ResourceRecordFormat) -> String {
+    let fields = resource_record_fields(resource);
+
+    match format {
+        ResourceRecordFormat::Text => format!(
+            "kind={} full_name={} package={} path={} suffix={}",
+            fields.kind,
+            escape_text_field(&fields.full_name),
+            escape_text_field(&fields.package),
+            escape_text_field(&fields.path),
+            escape_text_field(&fields.suffix),
+        ),
+        ResourceRecordFormat::Json => format!(
+            "{{\"kind\":{},\"full_name\":{},\"package\":{},\"path\":{},\"suffix\":{}}}",
+            escape_json_field(fields.kind),
+            escape_json_field(&fields.full_name),
+            escape_json_field(&fields.package),
+            escape_json_field(&fields.path),
+            escape_json_field(&fields.suffix),
+        ),
     }
+}
 
-    #[test]
-    fn test_site_packages() {
-        let td = tempdir::TempDir::new("pyoxidizer-test").unwrap();
-        let tp = td.path();
+/// Scan `root_path` and render every discovered resource as one diagnostic line,
+/// in the given [`ResourceRecordFormat`].
+///
+/// This is the `find-resources` entry point: a debugging/triage tool for seeing
+/// exactly what `PythonResourceIterator` produces for a directory or an unpacked
+/// distribution, without writing a throwaway program against the iterator.
+pub fn find_resources_report(
+    root_path: &Path,
+    suffixes: &PythonModuleSuffixes,
+    scan_archives: bool,
+    format: ResourceRecordFormat,
+) -> String {
+    find_python_resources(root_path, suffixes, scan_archives)
+        .map(|resource| format_resource_record(&resource, format))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-        let sp_path = tp.join("site-packages");
-        let acme_path = sp_path.join("acme");
+/// Controls which compiled extension modules are retained when collecting resources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtensionModuleFilter {
+    /// Only extension modules required to initialize the interpreter.
+    ///
+    /// Built-in extension modules are compiled into the interpreter itself and never
+    /// appear in a filesystem scan, so in practice this excludes every extension module
+    /// `find_python_resources` discovers.
+    Minimal,
 
-        create_dir_all(&acme_path).unwrap();
+    /// Every extension module encountered, regardless of what it links against.
+    All,
 
-        write(acme_path.join("__init__.py"), "").unwrap();
-        write(acme_path.join("bar.py"), "").unwrap();
+    /// Extension modules that don't link against any native libraries.
+    NoLibraries,
 
-        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
-        assert_eq!(resources.len(), 2);
+    /// Extension modules whose licenses are clear of copyleft obligations. A module
+    /// that links only against allow-listed system libraries is retained whatever
+    /// its license.
+    NoCopyleft,
+}
 
-        assert_eq!(
-            resources[0],
-            PythonFileResource::Source(SourceModule {
-                name: "acme".to_string(),
-                source: DataLocation::Path(acme_path.join("__init__.py")),
-                is_package: true,
-            })
-        );
-        assert_eq!(
-            resources[1],
-            PythonFileResource::Source(SourceModule {
-                name: "acme.bar".to_string(),
-                source: DataLocation::Path(acme_path.join("bar.py")),
-                is_package: false,
-            })
-        );
+/// Where a collected resource's data should live in the built application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceLocation {
+    /// Bundled into an in-memory resources blob, embedded in the binary.
+    InMemory,
+
+    /// Installed as a standalone file, relative to the application's install root.
+    RelativePath,
+}
+
+/// Policy controlling how scanned resources are collected into an application's Python tree.
+#[derive(Debug, Clone)]
+pub struct PythonResourcesPolicy {
+    /// Which extension modules to retain.
+    pub extension_module_filter: ExtensionModuleFilter,
+
+    /// Where to place retained resources.
+    pub resource_location: ResourceLocation,
+
+    /// Native library names exempt from `NoCopyleft`'s rejection, because they're
+    /// considered part of the base system rather than a bundled dependency (e.g. `c`,
+    /// `m`, `pthread`).
+    pub allowed_system_libraries: HashSet<String>,
+}
+
+impl Default for PythonResourcesPolicy {
+    fn default() -> Self {
+        PythonResourcesPolicy {
+            extension_module_filter: ExtensionModuleFilter::All,
+            resource_location: ResourceLocation::InMemory,
+            allowed_system_libraries: ["c", "m", "pthread", "dl", "rt", "util"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        }
     }
+}
 
-    #[test]
-    fn test_extension_module() -> Result<()> {
-        let td = tempdir::TempDir::new("pyoxidizer-test")?;
-        let tp = td.path();
+/// The native library dependencies and license metadata recovered for an extension module.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionModuleLicenseInfo {
+    /// SPDX-style license expressions applicable to the extension module.
+    pub licenses: Vec<String>,
 
-        create_dir_all(&tp.join("markupsafe"))?;
+    /// Names of the native (non-Python) libraries the extension module links against.
+    pub native_libraries: Vec<String>,
+}
 
-        let pyd_path = tp.join("foo.pyd");
-        let so_path = tp.join("bar.so");
-        let cffi_path = tp.join("_cffi_backend.cp37-win_amd64.pyd");
-        let markupsafe_speedups_path = tp
-            .join("markupsafe")
-            .join("_speedups.cpython-37m-x86_64-linux-gnu.so");
-        let zstd_path = tp.join("zstd.cpython-37m-x86_64-linux-gnu.so");
+/// A license recovered from a distribution's metadata, attributed to the component it covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentLicense {
+    /// Name of the distribution or extension module the license applies to.
+    pub component: String,
 
-        write(&pyd_path, "")?;
-        write(&so_path, "")?;
-        write(&cffi_path, "")?;
-        write(&markupsafe_speedups_path, "")?;
-        write(&zstd_path, "")?;
+    /// The license expression as recorded in the distribution's metadata.
+    pub license: String,
+}
 
-        let suffixes = PythonModuleSuffixes {
-            source: vec![],
-            bytecode: vec![],
-            debug_bytecode: vec![],
-            optimized_bytecode: vec![],
-            extension: vec![
-                ".cp37-win_amd64.pyd".to_string(),
-                ".cp37-win32.pyd".to_string(),
-                ".cpython-37m-x86_64-linux-gnu.so".to_string(),
-                ".pyd".to_string(),
-                ".so".to_string(),
-            ],
-        };
+/// Aggregated license information for a collected set of resources.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LicensedComponents {
+    licenses: Vec<ComponentLicense>,
+}
 
-        let resources = PythonResourceIterator::new(tp, &suffixes).collect_vec();
+impl LicensedComponents {
+    pub fn new() -> Self {
+        Default::default()
+    }
 
-        assert_eq!(resources.len(), 5);
+    /// Record that `component` is covered by `license`.
+    pub fn add_component(&mut self, component: impl Into<String>, license: impl Into<String>) {
+        self.licenses.push(ComponentLicense {
+            component: component.into(),
+            license: license.into(),
+        });
+    }
 
-        assert_eq!(
-            resources[0],
-            PythonFileResource::ExtensionModule {
-                package: "_cffi_backend".to_string(),
-                stem: "_cffi_backend".to_string(),
-                full_name: "_cffi_backend".to_string(),
-                path: cffi_path,
-                extension_file_suffix: ".cp37-win_amd64.pyd".to_string(),
+    /// The licenses recorded so far, one entry per license a component reported.
+    pub fn licenses(&self) -> &[ComponentLicense] {
+        &self.licenses
+    }
+}
+
+/// Determine whether an SPDX-style license expression is in the GPL/LGPL/AGPL copyleft family.
+fn is_copyleft_license(expression: &str) -> bool {
+    expression.to_uppercase().contains("GPL")
+}
+
+/// Collects `PythonFileResource` entries into a packaged manifest, applying a
+/// `PythonResourcesPolicy` along the way.
+///
+/// This is the bridge between the filesystem scanner and a built application: it decides
+/// which resources survive the configured extension module filter and where each
+/// retained resource's data should ultimately live, while tallying up the licenses of
+/// everything it keeps.
+pub struct PythonResourceCollection {
+    policy: PythonResourcesPolicy,
+    manifest: BTreeMap<String, ResourceLocation>,
+    licenses: LicensedComponents,
+}
+
+impl PythonResourceCollection {
+    pub fn new(policy: PythonResourcesPolicy) -> Self {
+        PythonResourceCollection {
+            policy,
+            manifest: BTreeMap::new(),
+            licenses: LicensedComponents::new(),
+        }
+    }
+
+    /// Consume a stream of scanned resources, applying the collection's policy.
+    ///
+    /// `extension_license_info` maps an extension module's full name to the native
+    /// library dependencies and licenses recovered for it; this is sourced separately
+    /// from the filesystem scan (e.g. by inspecting the built extension or its owning
+    /// distribution), since `PythonFileResource` doesn't carry that information itself.
+    pub fn collect(
+        &mut self,
+        resources: impl Iterator<Item = PythonFileResource>,
+        extension_license_info: &BTreeMap<String, ExtensionModuleLicenseInfo>,
+    ) -> Result<()> {
+        for resource in resources {
+            match resource {
+                PythonFileResource::Source(module) => {
+                    self.manifest.insert(module.name, self.policy.resource_location);
+                }
+                PythonFileResource::Bytecode(module) => {
+                    self.manifest
+                        .entry(module.name)
+                        .or_insert(self.policy.resource_location);
+                }
+                PythonFileResource::ExtensionModule { full_name, .. } => {
+                    if self.should_retain_extension(&full_name, extension_license_info) {
+                        if let Some(info) = extension_license_info.get(&full_name) {
+                            for license in &info.licenses {
+                                self.licenses.add_component(full_name.clone(), license.clone());
+                            }
+                        }
+
+                        self.manifest.insert(full_name, self.policy.resource_location);
+                    }
+                }
+                PythonFileResource::DistributionResource { package, name, path, .. }
+                    if name == "METADATA" || name == "PKG-INFO" =>
+                {
+                    // An unreadable metadata file shouldn't abort collection of
+                    // everything else; just forgo a license record for this one
+                    // distribution, consistent with how the rest of this module
+                    // favors best-effort classification over hard failure.
+                    let data = match path.resolve() {
+                        Ok(data) => data,
+                        Err(_) => continue,
+                    };
+                    let fields = parse_python_package_metadata(&data);
+
+                    if let Some(license) = fields.get("License") {
+                        if !license.is_empty() && license != "UNKNOWN" {
+                            self.licenses.add_component(package, license.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn should_retain_extension(
+        &self,
+        full_name: &str,
+        extension_license_info: &BTreeMap<String, ExtensionModuleLicenseInfo>,
+    ) -> bool {
+        match self.policy.extension_module_filter {
+            ExtensionModuleFilter::Minimal => false,
+            ExtensionModuleFilter::All => true,
+            ExtensionModuleFilter::NoLibraries => match extension_license_info.get(full_name) {
+                Some(info) => info.native_libraries.is_empty(),
+                None => true,
+            },
+            ExtensionModuleFilter::NoCopyleft => match extension_license_info.get(full_name) {
+                Some(info) => {
+                    let links_disallowed_library = info.native_libraries.iter().any(|lib| {
+                        !self.policy.allowed_system_libraries.contains(lib.as_str())
+                    });
+
+                    if !links_disallowed_library {
+                        true
+                    } else {
+                        !info.licenses.iter().any(|l| is_copyleft_license(l))
+                    }
+                }
+                None => true,
+            },
+        }
+    }
+
+    /// The manifest of retained resources, keyed by full module name.
+    pub fn manifest(&self) -> &BTreeMap<String, ResourceLocation> {
+        &self.manifest
+    }
+
+    /// The aggregated license report for every retained resource.
+    pub fn licenses(&self) -> &LicensedComponents {
+        &self.licenses
+    }
+}
+
+/// Magic bytes identifying a packed-resources blob, followed by a format version.
+const PACKED_RESOURCES_MAGIC: &[u8; 8] = b"PYREZ\0\0\x01";
+
+/// Which data section of a packed-resources blob an entry's bytes belong to.
+///
+/// Sections are concatenated in this order (`Source`, `Bytecode`, `ExtensionModule`,
+/// `PackageResource`) after the index, so an in-memory importer that only cares about
+/// one kind can read a contiguous run rather than hopping around the blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PackedResourceKind {
+    Source,
+    Bytecode,
+    ExtensionModule,
+    PackageResource,
+}
+
+impl PackedResourceKind {
+    fn tag(self) -> u8 {
+        match self {
+            PackedResourceKind::Source => 0,
+            PackedResourceKind::Bytecode => 1,
+            PackedResourceKind::ExtensionModule => 2,
+            PackedResourceKind::PackageResource => 3,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Result<Self> {
+        match tag {
+            0 => Ok(PackedResourceKind::Source),
+            1 => Ok(PackedResourceKind::Bytecode),
+            2 => Ok(PackedResourceKind::ExtensionModule),
+            3 => Ok(PackedResourceKind::PackageResource),
+            _ => Err(anyhow!("unrecognized packed resource kind tag: {}", tag)),
+        }
+    }
+}
+
+/// Where a collected resource's data should end up relative to the packed blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataPlacement {
+    /// The resource's bytes are embedded directly in the blob.
+    InBlob,
+
+    /// The resource is left as a standalone file; the blob's index only records that
+    /// it's external, and the bytes are returned via [`ResourceCollector::finish`]'s
+    /// external file manifest instead.
+    ExternalFile,
+}
+
+/// A file the caller must write alongside a packed-resources blob, because its
+/// resource was collected with [`DataPlacement::ExternalFile`].
+pub struct ExternalResourceFile {
+    /// Path of the file, relative to the blob's install root.
+    pub relative_path: PathBuf,
+
+    /// The file's data.
+    pub data: DataLocation,
+}
+
+/// One resource pending collection into a packed-resources blob.
+struct PendingEntry {
+    kind: PackedResourceKind,
+    is_package: bool,
+    data: DataLocation,
+    placement: DataPlacement,
+
+    /// Where this resource belongs on disk, relative to the install root, if it
+    /// ends up placed as an [`DataPlacement::ExternalFile`].
+    ///
+    /// This can't be derived from the dedup key (`entries`' `String` key) at
+    /// `finish()` time: that key is a dotted module/resource name, while the
+    /// filesystem path a resource's data should land at needs only *package*
+    /// separators turned into directories, with the leaf component (and any
+    /// literal `.` in its own file extension) left alone.
+    relative_path: PathBuf,
+}
+
+/// A dotted module name's path on disk, e.g. `"foo.bar"` -> `"foo/bar.py"`, or
+/// `"foo/__init__.py"` if `is_package` (since a package's own module lives inside
+/// the directory named after it, not beside it).
+fn module_relative_path(module_name: &str, is_package: bool, suffix: &str) -> PathBuf {
+    if is_package {
+        return PathBuf::from(module_name.replace('.', "/")).join(format!("__init__{}", suffix));
+    }
+
+    match module_name.rsplit_once('.') {
+        Some((package, leaf)) => {
+            PathBuf::from(package.replace('.', "/")).join(format!("{}{}", leaf, suffix))
+        }
+        None => PathBuf::from(format!("{}{}", module_name, suffix)),
+    }
+}
+
+/// Consumes scanned `PythonFileResource`s and serializes them into a single
+/// indexed, packed-resources blob: a header, an index of names with
+/// offsets/lengths/flags per resource kind, followed by the concatenated data of
+/// everything embedded in-blob.
+///
+/// This is the layer between the filesystem scan (or [`PythonResourceCollection`]'s
+/// policy decisions) and an in-memory importer: it turns a stream of
+/// `PythonFileResource` into bytes that importer can load directly, without
+/// re-walking the filesystem at runtime.
+pub struct ResourceCollector {
+    entries: BTreeMap<String, PendingEntry>,
+}
+
+impl ResourceCollector {
+    pub fn new() -> Self {
+        ResourceCollector {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Add a scanned resource, choosing where its data should live once packed.
+    ///
+    /// Resources that can't be packed (distribution metadata, eggs/wheels treated as
+    /// opaque files, `.pth` files, and the synthetic `NamespacePackage`/`Other`/
+    /// `ResourceFile` variants) are silently ignored; they aren't part of what an
+    /// in-memory importer needs to resolve modules and package resources.
+    ///
+    /// Entries are deduplicated by full module/resource name: the first one added
+    /// for a given name wins, matching how the scanner itself prefers the first
+    /// entry it encounters when namespace packages are resolved.
+    pub fn add(&mut self, resource: PythonFileResource, placement: DataPlacement) {
+        let (name, entry) = match resource {
+            PythonFileResource::Source(module) => {
+                let relative_path = module_relative_path(&module.name, module.is_package, ".py");
+
+                (
+                    module.name,
+                    PendingEntry {
+                        kind: PackedResourceKind::Source,
+                        is_package: module.is_package,
+                        data: module.source,
+                        placement,
+                        relative_path,
+                    },
+                )
+            }
+            PythonFileResource::Bytecode(module) => {
+                let relative_path = module_relative_path(&module.name, false, ".pyc");
+
+                (
+                    module.name,
+                    PendingEntry {
+                        kind: PackedResourceKind::Bytecode,
+                        is_package: false,
+                        data: module.bytecode,
+                        placement,
+                        relative_path,
+                    },
+                )
+            }
+            PythonFileResource::ExtensionModule {
+                full_name,
+                path,
+                extension_file_suffix,
+                ..
+            } => {
+                let relative_path =
+                    module_relative_path(&full_name, false, &extension_file_suffix);
+
+                (
+                    full_name,
+                    PendingEntry {
+                        kind: PackedResourceKind::ExtensionModule,
+                        is_package: false,
+                        data: DataLocation::Path(path),
+                        placement,
+                        relative_path,
+                    },
+                )
+            }
+            PythonFileResource::Resource(data) => {
+                let relative_path =
+                    PathBuf::from(data.leaf_package.replace('.', "/")).join(&data.relative_name);
+
+                (
+                    data.full_name,
+                    PendingEntry {
+                        kind: PackedResourceKind::PackageResource,
+                        is_package: false,
+                        data: data.data,
+                        placement,
+                        relative_path,
+                    },
+                )
+            }
+            _ => return,
+        };
+
+        self.entries.entry(name).or_insert(entry);
+    }
+
+    /// Insert an empty `__init__`-less package entry for every ancestor package that
+    /// doesn't already have an entry of its own.
+    ///
+    /// A module like `foo.bar.baz` implies that `foo` and `foo.bar` are packages, but
+    /// if neither shipped its own `__init__.py`/`.pyc` (a PEP 420 namespace package,
+    /// or simply an oversight in what got collected), an in-memory importer still
+    /// needs *some* entry for them to treat the dotted path as importable.
+    ///
+    /// Only module entries are considered: a package resource's name is a path, not
+    /// a dotted module name, so it implies no packages of its own.
+    fn fill_in_parent_packages(&mut self) {
+        let names = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.kind != PackedResourceKind::PackageResource)
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+
+        for name in names {
+            let mut parts = name.split('.').collect::<Vec<_>>();
+            parts.pop();
+
+            while !parts.is_empty() {
+                let package = itertools::join(&parts, ".");
+                let relative_path = module_relative_path(&package, true, ".py");
+
+                self.entries.entry(package).or_insert_with(|| PendingEntry {
+                    kind: PackedResourceKind::Source,
+                    is_package: true,
+                    data: DataLocation::Memory(Vec::new()),
+                    placement: DataPlacement::InBlob,
+                    relative_path,
+                });
+
+                parts.pop();
+            }
+        }
+    }
+
+    /// Serialize the collected resources into a packed-resources blob.
+    ///
+    /// Returns the blob and the manifest of files that must be written alongside it
+    /// (every entry collected with [`DataPlacement::ExternalFile`]).
+    pub fn finish(mut self) -> Result<(Vec<u8>, Vec<ExternalResourceFile>)> {
+        self.fill_in_parent_packages();
+
+        // Sorting by kind groups each data section together while a stable sort
+        // preserves the alphabetical-by-name order `entries` (a `BTreeMap`) already
+        // produced within each kind.
+        let mut entries = self.entries.into_iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(_, entry)| entry.kind);
+
+        let mut data_section = Vec::new();
+        let mut external_files = Vec::new();
+        let mut index = Vec::new();
+
+        for (name, entry) in entries {
+            let (offset, length, is_external) = match entry.placement {
+                DataPlacement::InBlob => {
+                    let data = entry.data.resolve()?;
+                    let offset = data_section.len() as u64;
+                    let length = data.len() as u64;
+                    data_section.extend_from_slice(&data);
+                    (offset, length, false)
+                }
+                DataPlacement::ExternalFile => {
+                    external_files.push(ExternalResourceFile {
+                        relative_path: entry.relative_path.clone(),
+                        data: entry.data,
+                    });
+                    (0, 0, true)
+                }
+            };
+
+            index.push((name, entry.kind, entry.is_package, is_external, offset, length));
+        }
+
+        let mut blob = Vec::new();
+        blob.extend_from_slice(PACKED_RESOURCES_MAGIC);
+        blob.extend_from_slice(&(index.len() as u32).to_le_bytes());
+
+        for (name, kind, is_package, is_external, offset, length) in &index {
+            let mut flags = 0u8;
+            if *is_package {
+                flags |= 0b0000_0001;
+            }
+            if *is_external {
+                flags |= 0b0000_0010;
             }
+
+            blob.push(kind.tag());
+            blob.push(flags);
+            blob.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            blob.extend_from_slice(name.as_bytes());
+            blob.extend_from_slice(&offset.to_le_bytes());
+            blob.extend_from_slice(&length.to_le_bytes());
+        }
+
+        blob.extend_from_slice(&data_section);
+
+        Ok((blob, external_files))
+    }
+}
+
+impl Default for ResourceCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A single entry decoded from a packed-resources blob's index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedResourceEntry {
+    pub kind: PackedResourceKind,
+    pub is_package: bool,
+    pub name: String,
+
+    /// The entry's data, if it was embedded in the blob. `None` means the entry was
+    /// collected with [`DataPlacement::ExternalFile`] and must be resolved via the
+    /// external file manifest returned alongside the blob instead.
+    pub data: Option<Vec<u8>>,
+}
+
+/// Decode the index and embedded data of a blob produced by [`ResourceCollector::finish`].
+pub fn read_packed_resources(blob: &[u8]) -> Result<Vec<PackedResourceEntry>> {
+    if blob.len() < PACKED_RESOURCES_MAGIC.len() + 4 {
+        return Err(anyhow!("packed resources blob is too short to contain a header"));
+    }
+
+    if &blob[0..PACKED_RESOURCES_MAGIC.len()] != PACKED_RESOURCES_MAGIC {
+        return Err(anyhow!("packed resources blob has an unrecognized magic header"));
+    }
+
+    let mut cursor = PACKED_RESOURCES_MAGIC.len();
+
+    let read_u32 = |cursor: &mut usize| -> Result<u32> {
+        let bytes = blob
+            .get(*cursor..*cursor + 4)
+            .ok_or_else(|| anyhow!("packed resources blob is truncated"))?;
+        *cursor += 4;
+        Ok(u32::from_le_bytes(bytes.try_into()?))
+    };
+    let read_u64 = |cursor: &mut usize| -> Result<u64> {
+        let bytes = blob
+            .get(*cursor..*cursor + 8)
+            .ok_or_else(|| anyhow!("packed resources blob is truncated"))?;
+        *cursor += 8;
+        Ok(u64::from_le_bytes(bytes.try_into()?))
+    };
+
+    let entry_count = read_u32(&mut cursor)?;
+
+    struct RawEntry {
+        kind: PackedResourceKind,
+        is_package: bool,
+        name: String,
+        is_external: bool,
+        offset: u64,
+        length: u64,
+    }
+
+    let mut raw_entries = Vec::with_capacity(entry_count as usize);
+
+    for _ in 0..entry_count {
+        let kind = PackedResourceKind::from_tag(
+            *blob
+                .get(cursor)
+                .ok_or_else(|| anyhow!("packed resources blob is truncated"))?,
+        )?;
+        cursor += 1;
+
+        let flags = *blob
+            .get(cursor)
+            .ok_or_else(|| anyhow!("packed resources blob is truncated"))?;
+        cursor += 1;
+
+        let name_len = read_u32(&mut cursor)? as usize;
+        let name_bytes = blob
+            .get(cursor..cursor + name_len)
+            .ok_or_else(|| anyhow!("packed resources blob is truncated"))?;
+        let name = String::from_utf8(name_bytes.to_vec())?;
+        cursor += name_len;
+
+        let offset = read_u64(&mut cursor)?;
+        let length = read_u64(&mut cursor)?;
+
+        raw_entries.push(RawEntry {
+            kind,
+            is_package: flags & 0b0000_0001 != 0,
+            name,
+            is_external: flags & 0b0000_0010 != 0,
+            offset,
+            length,
+        });
+    }
+
+    let data_section = &blob[cursor..];
+
+    raw_entries
+        .into_iter()
+        .map(|raw| {
+            let data = if raw.is_external {
+                None
+            } else {
+                Some(
+                    data_section
+                        .get(raw.offset as usize..(raw.offset + raw.length) as usize)
+                        .ok_or_else(|| anyhow!("packed resources blob data section is truncated"))?
+                        .to_vec(),
+                )
+            };
+
+            Ok(PackedResourceEntry {
+                kind: raw.kind,
+                is_package: raw.is_package,
+                name: raw.name,
+                data,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        lazy_static::lazy_static,
+        std::fs::{create_dir_all, write},
+        std::io::Write,
+    };
+
+    lazy_static! {
+        static ref EMPTY_SUFFIXES: PythonModuleSuffixes = PythonModuleSuffixes {
+            source: vec![],
+            bytecode: vec![],
+            debug_bytecode: vec![],
+            optimized_bytecode: vec![],
+            extension: vec![],
+        };
+    }
+
+    #[test]
+    fn test_source_resolution() {
+        let td = tempdir::TempDir::new("pyoxidizer-test").unwrap();
+        let tp = td.path();
+
+        let acme_path = tp.join("acme");
+        let acme_a_path = acme_path.join("a");
+        let acme_bar_path = acme_path.join("bar");
+
+        create_dir_all(&acme_a_path).unwrap();
+        create_dir_all(&acme_bar_path).unwrap();
+
+        write(acme_path.join("__init__.py"), "").unwrap();
+        write(acme_a_path.join("__init__.py"), "").unwrap();
+        write(acme_bar_path.join("__init__.py"), "").unwrap();
+
+        write(acme_a_path.join("foo.py"), "# acme.foo").unwrap();
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
+        assert_eq!(resources.len(), 4);
+
+        assert_eq!(
+            resources[0],
+            PythonFileResource::Source(SourceModule {
+                name: "acme".to_string(),
+                source: DataLocation::Path(acme_path.join("__init__.py")),
+                is_package: true,
+            })
         );
         assert_eq!(
             resources[1],
-            PythonFileResource::ExtensionModule {
-                package: "bar".to_string(),
-                stem: "bar".to_string(),
-                full_name: "bar".to_string(),
-                path: so_path,
-                extension_file_suffix: ".so".to_string(),
-            }
+            PythonFileResource::Source(SourceModule {
+                name: "acme.a".to_string(),
+                source: DataLocation::Path(acme_a_path.join("__init__.py")),
+                is_package: true,
+            })
         );
         assert_eq!(
             resources[2],
-            PythonFileResource::ExtensionModule {
-                package: "foo".to_string(),
-                stem: "foo".to_string(),
-                full_name: "foo".to_string(),
-                path: pyd_path,
-                extension_file_suffix: ".pyd".to_string(),
-            }
+            PythonFileResource::Source(SourceModule {
+                name: "acme.a.foo".to_string(),
+                source: DataLocation::Path(acme_a_path.join("foo.py")),
+                is_package: false,
+            })
+        );
+        assert_eq!(
+            resources[3],
+            PythonFileResource::Source(SourceModule {
+                name: "acme.bar".to_string(),
+                source: DataLocation::Path(acme_bar_path.join("__init__.py")),
+                is_package: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_site_packages() {
+        let td = tempdir::TempDir::new("pyoxidizer-test").unwrap();
+        let tp = td.path();
+
+        let sp_path = tp.join("site-packages");
+        let acme_path = sp_path.join("acme");
+
+        create_dir_all(&acme_path).unwrap();
+
+        write(acme_path.join("__init__.py"), "").unwrap();
+        write(acme_path.join("bar.py"), "").unwrap();
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
+        assert_eq!(resources.len(), 2);
+
+        assert_eq!(
+            resources[0],
+            PythonFileResource::Source(SourceModule {
+                name: "acme".to_string(),
+                source: DataLocation::Path(acme_path.join("__init__.py")),
+                is_package: true,
+            })
+        );
+        assert_eq!(
+            resources[1],
+            PythonFileResource::Source(SourceModule {
+                name: "acme.bar".to_string(),
+                source: DataLocation::Path(acme_path.join("bar.py")),
+                is_package: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_extension_module() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        create_dir_all(&tp.join("markupsafe"))?;
+
+        let pyd_path = tp.join("foo.pyd");
+        let so_path = tp.join("bar.so");
+        let cffi_path = tp.join("_cffi_backend.cp37-win_amd64.pyd");
+        let markupsafe_speedups_path = tp
+            .join("markupsafe")
+            .join("_speedups.cpython-37m-x86_64-linux-gnu.so");
+        let zstd_path = tp.join("zstd.cpython-37m-x86_64-linux-gnu.so");
+
+        write(&pyd_path, "")?;
+        write(&so_path, "")?;
+        write(&cffi_path, "")?;
+        write(&markupsafe_speedups_path, "")?;
+        write(&zstd_path, "")?;
+
+        let suffixes = PythonModuleSuffixes {
+            source: vec![],
+            bytecode: vec![],
+            debug_bytecode: vec![],
+            optimized_bytecode: vec![],
+            extension: vec![
+                ".cp37-win_amd64.pyd".to_string(),
+                ".cp37-win32.pyd".to_string(),
+                ".cpython-37m-x86_64-linux-gnu.so".to_string(),
+                ".pyd".to_string(),
+                ".so".to_string(),
+            ],
+        };
+
+        let resources = PythonResourceIterator::new(tp, &suffixes).collect_vec();
+
+        assert_eq!(resources.len(), 5);
+
+        assert_eq!(
+            resources[0],
+            PythonFileResource::ExtensionModule {
+                package: "_cffi_backend".to_string(),
+                stem: "_cffi_backend".to_string(),
+                full_name: "_cffi_backend".to_string(),
+                path: cffi_path,
+                extension_file_suffix: ".cp37-win_amd64.pyd".to_string(),
+            }
+        );
+        assert_eq!(
+            resources[1],
+            PythonFileResource::ExtensionModule {
+                package: "bar".to_string(),
+                stem: "bar".to_string(),
+                full_name: "bar".to_string(),
+                path: so_path,
+                extension_file_suffix: ".so".to_string(),
+            }
+        );
+        assert_eq!(
+            resources[2],
+            PythonFileResource::ExtensionModule {
+                package: "foo".to_string(),
+                stem: "foo".to_string(),
+                full_name: "foo".to_string(),
+                path: pyd_path,
+                extension_file_suffix: ".pyd".to_string(),
+            }
+        );
+        assert_eq!(
+            resources[3],
+            PythonFileResource::ExtensionModule {
+                package: "markupsafe".to_string(),
+                stem: "_speedups".to_string(),
+                full_name: "markupsafe._speedups".to_string(),
+                path: markupsafe_speedups_path,
+                extension_file_suffix: ".cpython-37m-x86_64-linux-gnu.so".to_string(),
+            }
+        );
+        assert_eq!(
+            resources[4],
+            PythonFileResource::ExtensionModule {
+                package: "zstd".to_string(),
+                stem: "zstd".to_string(),
+                full_name: "zstd".to_string(),
+                path: zstd_path,
+                extension_file_suffix: ".cpython-37m-x86_64-linux-gnu.so".to_string(),
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_egg_file() {
+        let td = tempdir::TempDir::new("pyoxidizer-test").unwrap();
+        let tp = td.path();
+
+        create_dir_all(&tp).unwrap();
+
+        let egg_path = tp.join("foo-1.0-py3.7.egg");
+        write(&egg_path, "").unwrap();
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
+        assert_eq!(resources.len(), 1);
+
+        assert_eq!(resources[0], PythonFileResource::EggFile { path: egg_path });
+    }
+
+    #[test]
+    fn test_egg_dir() {
+        let td = tempdir::TempDir::new("pyoxidizer-test").unwrap();
+        let tp = td.path();
+
+        create_dir_all(&tp).unwrap();
+
+        let egg_path = tp.join("site-packages").join("foo-1.0-py3.7.egg");
+        let egg_info_path = egg_path.join("EGG-INFO");
+        let package_path = egg_path.join("foo");
+
+        create_dir_all(&egg_info_path).unwrap();
+        create_dir_all(&package_path).unwrap();
+
+        write(egg_info_path.join("PKG-INFO"), "").unwrap();
+        write(package_path.join("__init__.py"), "").unwrap();
+        write(package_path.join("bar.py"), "").unwrap();
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
+        assert_eq!(resources.len(), 2);
+
+        assert_eq!(
+            resources[0],
+            PythonFileResource::Source(SourceModule {
+                name: "foo".to_string(),
+                source: DataLocation::Path(package_path.join("__init__.py")),
+                is_package: true,
+            })
+        );
+        assert_eq!(
+            resources[1],
+            PythonFileResource::Source(SourceModule {
+                name: "foo.bar".to_string(),
+                source: DataLocation::Path(package_path.join("bar.py")),
+                is_package: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_pth_file() {
+        let td = tempdir::TempDir::new("pyoxidizer-test").unwrap();
+        let tp = td.path();
+
+        create_dir_all(&tp).unwrap();
+
+        let pth_path = tp.join("foo.pth");
+        write(&pth_path, "").unwrap();
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
+        assert_eq!(resources.len(), 1);
+
+        assert_eq!(resources[0], PythonFileResource::PthFile { path: pth_path });
+    }
+
+    /// Resource files without a package are not valid.
+    #[test]
+    fn test_root_resource_file() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        let resource_path = tp.join("resource.txt");
+        write(&resource_path, "content")?;
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect::<Vec<_>>();
+        assert!(resources.is_empty());
+
+        Ok(())
+    }
+
+    /// A relative directory without an `__init__.py` that holds resource files is
+    /// an implicit namespace package, and its resources are addressed against it.
+    #[test]
+    fn test_relative_resource_no_package() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        write(&tp.join("foo.py"), "")?;
+        let resource_dir = tp.join("resources");
+        create_dir_all(&resource_dir)?;
+
+        let resource_path = resource_dir.join("resource.txt");
+        write(&resource_path, "content")?;
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect::<Vec<_>>();
+        assert_eq!(resources.len(), 3);
+
+        assert_eq!(
+            resources[0],
+            PythonFileResource::Source(SourceModule {
+                name: "foo".to_string(),
+                source: DataLocation::Path(tp.join("foo.py")),
+                is_package: false,
+            })
+        );
+        assert_eq!(
+            resources[1],
+            PythonFileResource::NamespacePackage {
+                name: "resources".to_string(),
+            }
+        );
+        assert_eq!(
+            resources[2],
+            PythonFileResource::Resource(ResourceData {
+                full_name: "resources/resource.txt".to_string(),
+                leaf_package: "resources".to_string(),
+                relative_name: "resource.txt".to_string(),
+                data: DataLocation::Path(resource_path),
+            })
+        );
+
+        Ok(())
+    }
+
+    /// Resource files next to a package are detected.
+    #[test]
+    fn test_relative_package_resource() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        let package_dir = tp.join("foo");
+        create_dir_all(&package_dir)?;
+
+        let module_path = package_dir.join("__init__.py");
+        write(&module_path, "")?;
+        let resource_path = package_dir.join("resource.txt");
+        write(&resource_path, "content")?;
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect::<Vec<_>>();
+        assert_eq!(
+            resources,
+            vec![
+                PythonFileResource::Source(SourceModule {
+                    name: "foo".to_string(),
+                    source: DataLocation::Path(module_path),
+                    is_package: true,
+                }),
+                PythonFileResource::Resource(ResourceData {
+                    full_name: "foo/resource.txt".to_string(),
+                    leaf_package: "foo".to_string(),
+                    relative_name: "resource.txt".to_string(),
+                    data: DataLocation::Path(resource_path),
+                })
+            ]
+        );
+
+        Ok(())
+    }
+
+    /// Resource files in sub-directory are detected. The sub-directory has no
+    /// `__init__.py`, so it is an implicit namespace package of its own.
+    #[test]
+    fn test_subdirectory_resource() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        let package_dir = tp.join("foo");
+        let subdir = package_dir.join("resources");
+        create_dir_all(&subdir)?;
+
+        let module_path = package_dir.join("__init__.py");
+        write(&module_path, "")?;
+        let resource_path = subdir.join("resource.txt");
+        write(&resource_path, "content")?;
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect::<Vec<_>>();
+        assert_eq!(
+            resources,
+            vec![
+                PythonFileResource::Source(SourceModule {
+                    name: "foo".to_string(),
+                    source: DataLocation::Path(module_path),
+                    is_package: true,
+                }),
+                PythonFileResource::NamespacePackage {
+                    name: "foo.resources".to_string(),
+                },
+                PythonFileResource::Resource(ResourceData {
+                    full_name: "foo/resources/resource.txt".to_string(),
+                    leaf_package: "foo.resources".to_string(),
+                    relative_name: "resource.txt".to_string(),
+                    data: DataLocation::Path(resource_path),
+                })
+            ]
+        );
+
+        Ok(())
+    }
+
+    /// A directory with nested packages but no `__init__.py` of its own is an
+    /// implicit PEP 420 namespace package.
+    #[test]
+    fn test_namespace_package() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        let ns_path = tp.join("ns");
+        let foo_path = ns_path.join("foo");
+        create_dir_all(&foo_path)?;
+
+        let module_path = foo_path.join("__init__.py");
+        write(&module_path, "")?;
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
+
+        assert_eq!(
+            resources,
+            vec![
+                PythonFileResource::Source(SourceModule {
+                    name: "ns.foo".to_string(),
+                    source: DataLocation::Path(module_path),
+                    is_package: true,
+                }),
+                PythonFileResource::NamespacePackage {
+                    name: "ns".to_string(),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_namespace_package_resource_only() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        let ns_path = tp.join("ns");
+        create_dir_all(&ns_path)?;
+
+        let data_path = ns_path.join("data.txt");
+        write(&data_path, "")?;
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
+
+        assert_eq!(
+            resources,
+            vec![
+                PythonFileResource::NamespacePackage {
+                    name: "ns".to_string(),
+                },
+                PythonFileResource::Resource(ResourceData {
+                    full_name: "ns/data.txt".to_string(),
+                    leaf_package: "ns".to_string(),
+                    relative_name: "data.txt".to_string(),
+                    data: DataLocation::Path(data_path),
+                }),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bytecode_module() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        let pycache_path = tp.join("foo").join("__pycache__");
+        create_dir_all(&pycache_path)?;
+
+        let unoptimized_path = pycache_path.join("bar.cpython-38.pyc");
+        let opt1_path = pycache_path.join("bar.cpython-38.opt-1.pyc");
+        write(&unoptimized_path, "")?;
+        write(&opt1_path, "")?;
+
+        let suffixes = PythonModuleSuffixes {
+            source: vec![],
+            bytecode: vec![".cpython-38.pyc".to_string()],
+            debug_bytecode: vec![],
+            optimized_bytecode: vec![
+                ".cpython-38.opt-1.pyc".to_string(),
+                ".cpython-38.opt-2.pyc".to_string(),
+            ],
+            extension: vec![],
+        };
+
+        let resources = PythonResourceIterator::new(tp, &suffixes).collect_vec();
+
+        assert_eq!(
+            resources,
+            vec![
+                // Entries are walked in file name order, and "...opt-1.pyc" sorts before
+                // "...pyc".
+                PythonFileResource::Bytecode(BytecodeModule::from_path(
+                    "foo.bar",
+                    BytecodeOptimizationLevel::One,
+                    &opt1_path,
+                )),
+                PythonFileResource::Bytecode(BytecodeModule::from_path(
+                    "foo.bar",
+                    BytecodeOptimizationLevel::Zero,
+                    &unoptimized_path,
+                )),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sourceless_bytecode_module() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        create_dir_all(&tp)?;
+
+        let module_path = tp.join("foo.pyc");
+        write(&module_path, "")?;
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
+
+        assert_eq!(
+            resources,
+            vec![PythonFileResource::Bytecode(BytecodeModule::from_path(
+                "foo",
+                BytecodeOptimizationLevel::Zero,
+                &module_path,
+            ))]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bytecode_module_unrecognized_cache_tag() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        let pycache_path = tp.join("foo").join("__pycache__");
+        create_dir_all(&pycache_path)?;
+
+        // Built for a different CPython ABI than the one whose suffixes we're
+        // scanning with, e.g. a tree containing bytecode for more than one
+        // interpreter. It should still be surfaced rather than dropped.
+        let other_abi_path = pycache_path.join("bar.cpython-38.opt-1.pyc");
+        write(&other_abi_path, "")?;
+
+        let suffixes = PythonModuleSuffixes {
+            source: vec![],
+            bytecode: vec![".cpython-39.pyc".to_string()],
+            debug_bytecode: vec![],
+            optimized_bytecode: vec![
+                ".cpython-39.opt-1.pyc".to_string(),
+                ".cpython-39.opt-2.pyc".to_string(),
+            ],
+            extension: vec![],
+        };
+
+        let resources = PythonResourceIterator::new(tp, &suffixes).collect_vec();
+
+        assert_eq!(
+            resources,
+            vec![PythonFileResource::Bytecode(BytecodeModule::from_path(
+                "foo.bar",
+                BytecodeOptimizationLevel::One,
+                &other_abi_path,
+            ))]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_distribution_metadata_loose_directories() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        let dist_info_path = tp.join("foo-1.0.dist-info");
+        create_dir_all(&dist_info_path)?;
+        write(dist_info_path.join("METADATA"), "Name: foo\nVersion: 1.0\n")?;
+        write(dist_info_path.join("RECORD"), "")?;
+
+        // `.egg-info` directories aren't guaranteed a `-<version>` suffix; the
+        // package name should still come through with no version parsed.
+        let egg_info_path = tp.join("bar.egg-info");
+        create_dir_all(&egg_info_path)?;
+        write(egg_info_path.join("PKG-INFO"), "")?;
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
+
+        assert_eq!(
+            resources,
+            vec![
+                // Entries are walked in file name order, and "bar.egg-info" sorts
+                // before "foo-1.0.dist-info".
+                PythonFileResource::DistributionResource {
+                    package: "bar".to_string(),
+                    version: None,
+                    name: "PKG-INFO".to_string(),
+                    flavor: DistributionMetadataFlavor::EggInfo,
+                    path: DataLocation::Path(egg_info_path.join("PKG-INFO")),
+                },
+                PythonFileResource::DistributionResource {
+                    package: "foo".to_string(),
+                    version: Some("1.0".to_string()),
+                    name: "METADATA".to_string(),
+                    flavor: DistributionMetadataFlavor::DistInfo,
+                    path: DataLocation::Path(dist_info_path.join("METADATA")),
+                },
+                PythonFileResource::DistributionResource {
+                    package: "foo".to_string(),
+                    version: Some("1.0".to_string()),
+                    name: "RECORD".to_string(),
+                    flavor: DistributionMetadataFlavor::DistInfo,
+                    path: DataLocation::Path(dist_info_path.join("RECORD")),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_distribution_metadata_under_site_packages() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        let sp_path = tp.join("site-packages");
+        let dist_info_path = sp_path.join("foo-1.0.dist-info");
+        create_dir_all(&dist_info_path)?;
+        write(dist_info_path.join("METADATA"), "Name: foo\nVersion: 1.0\n")?;
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
+
+        assert_eq!(
+            resources,
+            vec![PythonFileResource::DistributionResource {
+                package: "foo".to_string(),
+                version: Some("1.0".to_string()),
+                name: "METADATA".to_string(),
+                flavor: DistributionMetadataFlavor::DistInfo,
+                path: DataLocation::Path(dist_info_path.join("METADATA")),
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wheel_scanning() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        create_dir_all(&tp)?;
+
+        let wheel_path = tp.join("foo-1.0-py3-none-any.whl");
+        let writer_file = std::fs::File::create(&wheel_path)?;
+        let mut writer = zip::ZipWriter::new(writer_file);
+        let options = zip::write::FileOptions::default();
+
+        writer.start_file("foo/__init__.py", options)?;
+        writer.write_all(b"")?;
+
+        writer.start_file("foo/bar.py", options)?;
+        writer.write_all(b"")?;
+
+        // pip installs `.data/purelib` members straight into `site-packages`.
+        writer.start_file("foo-1.0.data/purelib/extra.py", options)?;
+        writer.write_all(b"")?;
+
+        // ...and `.data/scripts` members into the scripts directory.
+        writer.start_file("foo-1.0.data/scripts/foo-cli", options)?;
+        writer.write_all(b"#!/usr/bin/env python\n")?;
+
+        writer.start_file("foo-1.0.dist-info/METADATA", options)?;
+        writer.write_all(b"Name: foo\nVersion: 1.0\n")?;
+
+        // Compiled bytecode ships alongside the source in some wheels too.
+        writer.start_file("foo/__pycache__/bar.cpython-38.pyc", options)?;
+        writer.write_all(b"")?;
+
+        writer.finish()?;
+
+        // With archive scanning disabled (the default), the wheel is opaque.
+        let resources = find_python_resources(tp, &EMPTY_SUFFIXES, false).collect_vec();
+        assert_eq!(
+            resources,
+            vec![PythonFileResource::WheelFile {
+                path: wheel_path.clone()
+            }]
+        );
+
+        // With archive scanning enabled, its members are classified like loose files.
+        let resources = find_python_resources(tp, &EMPTY_SUFFIXES, true).collect_vec();
+
+        assert!(resources.contains(&PythonFileResource::Source(SourceModule {
+            name: "foo".to_string(),
+            source: DataLocation::Memory(vec![]),
+            is_package: true,
+        })));
+        assert!(resources.contains(&PythonFileResource::Source(SourceModule {
+            name: "foo.bar".to_string(),
+            source: DataLocation::Memory(vec![]),
+            is_package: false,
+        })));
+        assert!(resources.contains(&PythonFileResource::Source(SourceModule {
+            name: "extra".to_string(),
+            source: DataLocation::Memory(vec![]),
+            is_package: false,
+        })));
+        assert!(resources.contains(&PythonFileResource::Bytecode(BytecodeModule {
+            name: "foo.bar".to_string(),
+            bytecode: DataLocation::Memory(vec![]),
+            optimization_level: BytecodeOptimizationLevel::Zero,
+        })));
+        assert!(resources.contains(&PythonFileResource::DistributionResource {
+            package: "foo".to_string(),
+            version: Some("1.0".to_string()),
+            name: "METADATA".to_string(),
+            flavor: DistributionMetadataFlavor::DistInfo,
+            path: DataLocation::Memory(b"Name: foo\nVersion: 1.0\n".to_vec()),
+        }));
+        // `.data/scripts` members are addressed under a `scripts` directory, same as
+        // `.data/purelib`/`.data/platlib` members are addressed under `site-packages`.
+        // Archive scanning doesn't specialize script wrappers into `PathExtension`
+        // (see `scan_archive`'s doc comment), so this surfaces as a plain resource.
+        assert!(resources.contains(&PythonFileResource::Resource(ResourceData {
+            full_name: "scripts/foo-cli".to_string(),
+            leaf_package: "scripts".to_string(),
+            relative_name: "foo-cli".to_string(),
+            data: DataLocation::Memory(b"#!/usr/bin/env python\n".to_vec()),
+        })));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collection_no_copyleft_filter() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        create_dir_all(&tp)?;
+
+        let suffixes = PythonModuleSuffixes {
+            source: vec![],
+            bytecode: vec![],
+            debug_bytecode: vec![],
+            optimized_bytecode: vec![],
+            extension: vec![".so".to_string()],
+        };
+
+        write(tp.join("gpl_ext.so"), "")?;
+        write(tp.join("mit_ext.so"), "")?;
+        write(tp.join("system_ext.so"), "")?;
+
+        let mut extension_license_info = BTreeMap::new();
+        extension_license_info.insert(
+            "gpl_ext".to_string(),
+            ExtensionModuleLicenseInfo {
+                licenses: vec!["GPL-3.0".to_string()],
+                native_libraries: vec!["ssl".to_string()],
+            },
+        );
+        extension_license_info.insert(
+            "mit_ext".to_string(),
+            ExtensionModuleLicenseInfo {
+                licenses: vec!["MIT".to_string()],
+                native_libraries: vec!["ssl".to_string()],
+            },
+        );
+        extension_license_info.insert(
+            "system_ext".to_string(),
+            ExtensionModuleLicenseInfo {
+                licenses: vec!["GPL-3.0".to_string()],
+                native_libraries: vec!["c".to_string()],
+            },
         );
+
+        let policy = PythonResourcesPolicy {
+            extension_module_filter: ExtensionModuleFilter::NoCopyleft,
+            ..Default::default()
+        };
+        let mut collection = PythonResourceCollection::new(policy);
+        collection.collect(
+            find_python_resources(tp, &suffixes, false),
+            &extension_license_info,
+        )?;
+
+        let manifest = collection.manifest();
+        assert!(!manifest.contains_key("gpl_ext"));
+        assert!(manifest.contains_key("mit_ext"));
+        // Only links against an allow-listed system library, so its copyleft license
+        // doesn't disqualify it.
+        assert!(manifest.contains_key("system_ext"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collection_license_report() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        let dist_info = tp.join("foo-1.0.dist-info");
+        create_dir_all(&dist_info)?;
+        write(dist_info.join("METADATA"), "Name: foo\nLicense: MIT\n")?;
+
+        let mut collection = PythonResourceCollection::new(PythonResourcesPolicy::default());
+        collection.collect(
+            find_python_resources(tp, &EMPTY_SUFFIXES, false),
+            &BTreeMap::new(),
+        )?;
+
         assert_eq!(
-            resources[3],
-            PythonFileResource::ExtensionModule {
-                package: "markupsafe".to_string(),
-                stem: "_speedups".to_string(),
-                full_name: "markupsafe._speedups".to_string(),
-                path: markupsafe_speedups_path,
-                extension_file_suffix: ".cpython-37m-x86_64-linux-gnu.so".to_string(),
-            }
+            collection.licenses().licenses(),
+            &[ComponentLicense {
+                component: "foo".to_string(),
+                license: "MIT".to_string(),
+            }]
         );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collection_license_report_from_wheel() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        let wheel_path = tp.join("foo-1.0-py3-none-any.whl");
+        let writer_file = std::fs::File::create(&wheel_path)?;
+        let mut writer = zip::ZipWriter::new(writer_file);
+        let options = zip::write::FileOptions::default();
+
+        writer.start_file("foo-1.0.dist-info/METADATA", options)?;
+        writer.write_all(b"Name: foo\nLicense: MIT\n")?;
+
+        writer.finish()?;
+
+        // A `DistributionResource` sourced from inside an archive carries its
+        // `METADATA` bytes in memory rather than a path on disk; `collect()` must
+        // read it without touching the filesystem.
+        let mut collection = PythonResourceCollection::new(PythonResourcesPolicy::default());
+        collection.collect(
+            find_python_resources(tp, &EMPTY_SUFFIXES, true),
+            &BTreeMap::new(),
+        )?;
+
         assert_eq!(
-            resources[4],
-            PythonFileResource::ExtensionModule {
-                package: "zstd".to_string(),
-                stem: "zstd".to_string(),
-                full_name: "zstd".to_string(),
-                path: zstd_path,
-                extension_file_suffix: ".cpython-37m-x86_64-linux-gnu.so".to_string(),
-            }
+            collection.licenses().licenses(),
+            &[ComponentLicense {
+                component: "foo".to_string(),
+                license: "MIT".to_string(),
+            }]
         );
 
         Ok(())
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_egg_file() {
+    fn test_scan_non_utf8_path_is_lossy_not_fatal() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
         let td = tempdir::TempDir::new("pyoxidizer-test").unwrap();
         let tp = td.path();
 
-        create_dir_all(&tp).unwrap();
+        let bogus_name = OsStr::from_bytes(b"\xff\xfe.py");
+        write(tp.join(bogus_name), "").unwrap();
 
-        let egg_path = tp.join("foo-1.0-py3.7.egg");
-        write(&egg_path, "").unwrap();
+        let mut iter = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES);
+        let resources = (&mut iter).collect_vec();
 
-        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
+        // The entry is still yielded, using a lossily-converted module name, and the
+        // substitution is recorded rather than silently swallowed.
         assert_eq!(resources.len(), 1);
-
-        assert_eq!(resources[0], PythonFileResource::EggFile { path: egg_path });
+        assert_eq!(iter.errors().len(), 1);
+        assert_eq!(iter.errors()[0].path, tp.join(bogus_name));
     }
 
     #[test]
-    fn test_egg_dir() {
-        let td = tempdir::TempDir::new("pyoxidizer-test").unwrap();
+    fn test_path_filter_deny_prunes_directory() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
         let tp = td.path();
 
-        create_dir_all(&tp).unwrap();
+        write(tp.join("foo.py"), "")?;
 
-        let egg_path = tp.join("site-packages").join("foo-1.0-py3.7.egg");
-        let egg_info_path = egg_path.join("EGG-INFO");
-        let package_path = egg_path.join("foo");
+        let vendor_path = tp.join("vendor");
+        create_dir_all(&vendor_path)?;
+        write(vendor_path.join("bar.py"), "")?;
 
-        create_dir_all(&egg_info_path).unwrap();
-        create_dir_all(&package_path).unwrap();
+        let filter = PathFilter::new(&[], &["vendor/**"])?;
 
-        write(egg_info_path.join("PKG-INFO"), "").unwrap();
-        write(package_path.join("__init__.py"), "").unwrap();
-        write(package_path.join("bar.py"), "").unwrap();
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES)
+            .with_filter(filter)
+            .collect_vec();
 
-        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
-        assert_eq!(resources.len(), 2);
+        assert_eq!(
+            resources,
+            vec![PythonFileResource::Source(SourceModule {
+                name: "foo".to_string(),
+                source: DataLocation::Path(tp.join("foo.py")),
+                is_package: false,
+            })]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_filter_allow_list() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        write(tp.join("foo.py"), "")?;
+        write(tp.join("data.json"), "")?;
+
+        let filter = PathFilter::new(&["*.py"], &[])?;
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES)
+            .with_filter(filter)
+            .collect_vec();
 
         assert_eq!(
-            resources[0],
-            PythonFileResource::Source(SourceModule {
+            resources,
+            vec![PythonFileResource::Source(SourceModule {
                 name: "foo".to_string(),
-                source: DataLocation::Path(package_path.join("__init__.py")),
-                is_package: true,
-            })
+                source: DataLocation::Path(tp.join("foo.py")),
+                is_package: false,
+            })]
         );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_resource_record() -> Result<()> {
+        let resource = PythonFileResource::Source(SourceModule {
+            name: "foo.bar".to_string(),
+            source: DataLocation::Path(PathBuf::from("/site-packages/foo/bar.py")),
+            is_package: false,
+        });
+
         assert_eq!(
-            resources[1],
+            format_resource_record(&resource, ResourceRecordFormat::Text),
+            "kind=source full_name=foo.bar package=foo path=/site-packages/foo/bar.py suffix=.py"
+        );
+        assert_eq!(
+            format_resource_record(&resource, ResourceRecordFormat::Json),
+            r#"{"kind":"source","full_name":"foo.bar","package":"foo","path":"/site-packages/foo/bar.py","suffix":".py"}"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_resource_record_json_escaping() -> Result<()> {
+        // A control byte and the lossy-UTF8 replacement character, to prove the
+        // `Json` format emits valid `\u00XX` escapes rather than Debug's
+        // `\u{7}`-style braced, variable-width ones.
+        let name = "foo\u{7}bar\u{fffd}baz".to_string();
+        let resource = PythonFileResource::Source(SourceModule {
+            name: name.clone(),
+            source: DataLocation::Path(PathBuf::from("/site-packages/foo.py")),
+            is_package: false,
+        });
+
+        let json = format_resource_record(&resource, ResourceRecordFormat::Json);
+        assert!(json.contains("\\u0007"), "expected \\u0007 escape in {}", json);
+        assert!(!json.contains("\\u{"), "Debug-style brace escape leaked into {}", json);
+
+        // Decode the `full_name` field's JSON string value back to prove it's
+        // actually valid JSON, not just Debug output that happens to look similar.
+        let start = json.find("\"full_name\":\"").unwrap() + "\"full_name\":\"".len();
+        let rest = &json[start..];
+        let end = rest.find("\",\"package\"").unwrap();
+        let encoded = &rest[..end];
+
+        let mut decoded = String::new();
+        let mut chars = encoded.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                match chars.next() {
+                    Some('n') => decoded.push('\n'),
+                    Some('r') => decoded.push('\r'),
+                    Some('t') => decoded.push('\t'),
+                    Some('"') => decoded.push('"'),
+                    Some('\\') => decoded.push('\\'),
+                    Some('u') => {
+                        let hex: String = (0..4).map(|_| chars.next().unwrap()).collect();
+                        let code = u32::from_str_radix(&hex, 16)?;
+                        decoded.push(char::from_u32(code).unwrap());
+                    }
+                    other => panic!("unexpected JSON escape: {:?}", other),
+                }
+            } else {
+                decoded.push(c);
+            }
+        }
+
+        assert_eq!(decoded, name);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_resources_report() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        write(tp.join("foo.py"), "")?;
+
+        let report = find_resources_report(tp, &EMPTY_SUFFIXES, false, ResourceRecordFormat::Text);
+
+        assert_eq!(
+            report,
+            format!(
+                "kind=source full_name=foo package= path={} suffix=.py",
+                tp.join("foo.py").display()
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resource_collector_roundtrip() -> Result<()> {
+        let mut collector = ResourceCollector::new();
+
+        collector.add(
             PythonFileResource::Source(SourceModule {
                 name: "foo.bar".to_string(),
-                source: DataLocation::Path(package_path.join("bar.py")),
+                source: DataLocation::Memory(b"source code".to_vec()),
                 is_package: false,
-            })
+            }),
+            DataPlacement::InBlob,
+        );
+        collector.add(
+            PythonFileResource::Resource(ResourceData {
+                full_name: "foo.data.txt".to_string(),
+                leaf_package: "foo".to_string(),
+                relative_name: "data.txt".to_string(),
+                data: DataLocation::Memory(b"resource bytes".to_vec()),
+            }),
+            DataPlacement::ExternalFile,
+        );
+
+        let (blob, external_files) = collector.finish()?;
+
+        assert_eq!(external_files.len(), 1);
+        assert_eq!(external_files[0].relative_path, PathBuf::from("foo/data.txt"));
+        assert_eq!(external_files[0].data.resolve()?, b"resource bytes");
+
+        let entries = read_packed_resources(&blob)?;
+
+        // `foo` has no explicit entry of its own, so it's auto-created as an empty
+        // package so `foo.bar` resolves as importable.
+        assert_eq!(
+            entries,
+            vec![
+                PackedResourceEntry {
+                    kind: PackedResourceKind::Source,
+                    is_package: true,
+                    name: "foo".to_string(),
+                    data: Some(Vec::new()),
+                },
+                PackedResourceEntry {
+                    kind: PackedResourceKind::Source,
+                    is_package: false,
+                    name: "foo.bar".to_string(),
+                    data: Some(b"source code".to_vec()),
+                },
+                PackedResourceEntry {
+                    kind: PackedResourceKind::PackageResource,
+                    is_package: false,
+                    name: "foo.data.txt".to_string(),
+                    data: None,
+                },
+            ]
         );
+
+        Ok(())
     }
 
     #[test]
-    fn test_pth_file() {
-        let td = tempdir::TempDir::new("pyoxidizer-test").unwrap();
-        let tp = td.path();
+    fn test_resource_collector_dedup_first_wins() -> Result<()> {
+        let mut collector = ResourceCollector::new();
 
-        create_dir_all(&tp).unwrap();
+        collector.add(
+            PythonFileResource::Source(SourceModule {
+                name: "foo".to_string(),
+                source: DataLocation::Memory(b"first".to_vec()),
+                is_package: false,
+            }),
+            DataPlacement::InBlob,
+        );
+        collector.add(
+            PythonFileResource::Source(SourceModule {
+                name: "foo".to_string(),
+                source: DataLocation::Memory(b"second".to_vec()),
+                is_package: false,
+            }),
+            DataPlacement::InBlob,
+        );
 
-        let pth_path = tp.join("foo.pth");
-        write(&pth_path, "").unwrap();
+        let (blob, _) = collector.finish()?;
+        let entries = read_packed_resources(&blob)?;
 
-        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
-        assert_eq!(resources.len(), 1);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].data, Some(b"first".to_vec()));
 
-        assert_eq!(resources[0], PythonFileResource::PthFile { path: pth_path });
+        Ok(())
     }
 
-    /// Resource files without a package are not valid.
     #[test]
-    fn test_root_resource_file() -> Result<()> {
+    fn test_resource_collector_external_extension_module_path() -> Result<()> {
+        let mut collector = ResourceCollector::new();
+
+        collector.add(
+            PythonFileResource::ExtensionModule {
+                package: "foo".to_string(),
+                stem: "bar".to_string(),
+                full_name: "foo.bar".to_string(),
+                path: PathBuf::from("/nonexistent/bar.cpython-37m-x86_64-linux-gnu.so"),
+                extension_file_suffix: ".cpython-37m-x86_64-linux-gnu.so".to_string(),
+            },
+            DataPlacement::ExternalFile,
+        );
+
+        let (_, external_files) = collector.finish()?;
+
+        assert_eq!(external_files.len(), 1);
+        assert_eq!(
+            external_files[0].relative_path,
+            PathBuf::from("foo/bar.cpython-37m-x86_64-linux-gnu.so")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_library_without_matching_suffix() -> Result<()> {
         let td = tempdir::TempDir::new("pyoxidizer-test")?;
         let tp = td.path();
 
-        let resource_path = tp.join("resource.txt");
-        write(&resource_path, "content")?;
+        let package_path = tp.join("foo");
+        create_dir_all(&package_path)?;
 
-        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect::<Vec<_>>();
-        assert!(resources.is_empty());
+        // No suffix is registered for `.so`, so this can't be a recognized extension
+        // module; it should surface as a `SharedLibrary` rather than an opaque resource.
+        let lib_path = package_path.join("libbar.so");
+        write(&lib_path, "")?;
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
+
+        assert_eq!(
+            resources,
+            vec![PythonFileResource::SharedLibrary {
+                name: "foo.libbar.so".to_string(),
+                package: "foo".to_string(),
+                path: lib_path,
+            }]
+        );
 
         Ok(())
     }
 
-    /// Resource files in a relative directory without a package are not valid.
     #[test]
-    fn test_relative_resource_no_package() -> Result<()> {
+    fn test_shared_library_versioned_filename() -> Result<()> {
         let td = tempdir::TempDir::new("pyoxidizer-test")?;
         let tp = td.path();
 
-        write(&tp.join("foo.py"), "")?;
-        let resource_dir = tp.join("resources");
-        create_dir_all(&resource_dir)?;
+        let package_path = tp.join("foo");
+        create_dir_all(&package_path)?;
 
-        let resource_path = resource_dir.join("resource.txt");
-        write(&resource_path, "content")?;
+        // Linux shared libraries are commonly bundled with a version suffix, e.g.
+        // `libbar.so.1.2.3`. `Path::extension()` would only see `3` here, so this
+        // must be recognized by inspecting the whole file name.
+        let lib_path = package_path.join("libbar.so.1.2.3");
+        write(&lib_path, "")?;
 
-        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect::<Vec<_>>();
-        assert_eq!(resources.len(), 1);
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
 
         assert_eq!(
-            resources[0],
-            PythonFileResource::Source(SourceModule {
-                name: "foo".to_string(),
-                source: DataLocation::Path(tp.join("foo.py")),
-                is_package: false,
-            })
+            resources,
+            vec![PythonFileResource::SharedLibrary {
+                name: "foo.libbar.so.1.2.3".to_string(),
+                package: "foo".to_string(),
+                path: lib_path,
+            }]
         );
 
         Ok(())
     }
 
-    /// Resource files next to a package are detected.
     #[test]
-    fn test_relative_package_resource() -> Result<()> {
+    fn test_path_extension_script() -> Result<()> {
         let td = tempdir::TempDir::new("pyoxidizer-test")?;
         let tp = td.path();
 
-        let package_dir = tp.join("foo");
-        create_dir_all(&package_dir)?;
+        let bin_path = tp.join("bin");
+        create_dir_all(&bin_path)?;
 
-        let module_path = package_dir.join("__init__.py");
-        write(&module_path, "")?;
-        let resource_path = package_dir.join("resource.txt");
-        write(&resource_path, "content")?;
+        let script_path = bin_path.join("mytool");
+        write(&script_path, "#!/bin/sh\n")?;
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
 
-        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect::<Vec<_>>();
         assert_eq!(
             resources,
-            vec![
-                PythonFileResource::Source(SourceModule {
-                    name: "foo".to_string(),
-                    source: DataLocation::Path(module_path),
-                    is_package: true,
-                }),
-                PythonFileResource::Resource(ResourceData {
-                    full_name: "foo/resource.txt".to_string(),
-                    leaf_package: "foo".to_string(),
-                    relative_name: "resource.txt".to_string(),
-                    data: DataLocation::Path(resource_path),
-                })
-            ]
+            vec![PythonFileResource::PathExtension {
+                name: "bin.mytool".to_string(),
+                package: "bin".to_string(),
+                path: script_path,
+                is_executable: false,
+            }]
         );
 
         Ok(())
     }
 
-    /// Resource files in sub-directory are detected.
+    #[cfg(unix)]
     #[test]
-    fn test_subdirectory_resource() -> Result<()> {
+    fn test_path_extension_script_is_executable() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
         let td = tempdir::TempDir::new("pyoxidizer-test")?;
         let tp = td.path();
 
-        let package_dir = tp.join("foo");
-        let subdir = package_dir.join("resources");
-        create_dir_all(&subdir)?;
+        let bin_path = tp.join("bin");
+        create_dir_all(&bin_path)?;
 
-        let module_path = package_dir.join("__init__.py");
-        write(&module_path, "")?;
-        let resource_path = subdir.join("resource.txt");
-        write(&resource_path, "content")?;
+        let script_path = bin_path.join("mytool");
+        write(&script_path, "#!/bin/sh\n")?;
+        std::fs::set_permissions(&script_path, std::fs::Permissions::from_mode(0o755))?;
+
+        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect_vec();
 
-        let resources = PythonResourceIterator::new(tp, &EMPTY_SUFFIXES).collect::<Vec<_>>();
         assert_eq!(
             resources,
-            vec![
-                PythonFileResource::Source(SourceModule {
-                    name: "foo".to_string(),
-                    source: DataLocation::Path(module_path),
-                    is_package: true,
-                }),
-                PythonFileResource::Resource(ResourceData {
-                    full_name: "foo/resources/resource.txt".to_string(),
-                    leaf_package: "foo".to_string(),
-                    relative_name: "resources/resource.txt".to_string(),
-                    data: DataLocation::Path(resource_path),
-                })
-            ]
+            vec![PythonFileResource::PathExtension {
+                name: "bin.mytool".to_string(),
+                package: "bin".to_string(),
+                path: script_path,
+                is_executable: true,
+            }]
         );
 
         Ok(())
@@ -9,6 +9,8 @@ use {
     super::target::{BuildContext, BuildTarget, ResolvedTarget},
     super::util::{optional_list_arg, required_bool_arg, required_str_arg, required_type_arg},
     anyhow::{anyhow, Context, Result},
+    codemap::CodeMap,
+    codemap_diagnostic::{ColorConfig, Diagnostic, Emitter, Level, SpanLabel, SpanStyle},
     linked_hash_map::LinkedHashMap,
     path_dedot::ParseDot,
     slog::warn,
@@ -21,10 +23,94 @@ use {
         starlark_fun, starlark_module, starlark_param_name, starlark_parse_param_type,
         starlark_signature, starlark_signature_extraction, starlark_signatures,
     },
-    std::collections::BTreeMap,
+    petgraph::algo::toposort,
+    petgraph::graph::{DiGraph, NodeIndex},
+    std::cell::RefCell,
+    std::collections::{BTreeMap, HashSet},
     std::path::{Path, PathBuf},
+    std::rc::Rc,
 };
 
+/// A Starlark value type that can be built (and optionally run) as a target.
+///
+/// Implementing this trait and registering a constructor function via
+/// `EnvironmentContext::register_target_type()` is how new Starlark value
+/// types become buildable/runnable targets without teaching
+/// `build_resolved_target()` about them via a hardcoded type match. This
+/// keeps the target resolution machinery generic and reusable independent of
+/// any Python-specific value types.
+pub trait BuildableTarget {
+    /// Build this value, turning it into a `ResolvedTarget`.
+    fn build(&mut self, context: &BuildContext) -> Result<ResolvedTarget>;
+}
+
+/// Constructs a `BuildableTarget` wrapping a Starlark `Value`, if the value
+/// is of the expected type.
+pub type BuildableTargetConstructor =
+    Box<dyn Fn(&Value) -> Option<Box<dyn BuildableTarget>> + Send + 'static>;
+
+struct FileManifestTarget(Value);
+
+impl BuildableTarget for FileManifestTarget {
+    fn build(&mut self, context: &BuildContext) -> Result<ResolvedTarget> {
+        self.0
+            .downcast_mut::<FileManifest>()
+            .map_err(|_| anyhow!("object isn't mutable"))?
+            .ok_or_else(|| anyhow!("invalid cast"))?
+            .build(context)
+    }
+}
+
+struct PythonExecutableTarget(Value);
+
+impl BuildableTarget for PythonExecutableTarget {
+    fn build(&mut self, context: &BuildContext) -> Result<ResolvedTarget> {
+        self.0
+            .downcast_mut::<PythonExecutable>()
+            .map_err(|_| anyhow!("object isn't mutable"))?
+            .ok_or_else(|| anyhow!("invalid cast"))?
+            .build(context)
+    }
+}
+
+struct PythonEmbeddedResourcesTarget(Value);
+
+impl BuildableTarget for PythonEmbeddedResourcesTarget {
+    fn build(&mut self, context: &BuildContext) -> Result<ResolvedTarget> {
+        self.0
+            .downcast_mut::<PythonEmbeddedResources>()
+            .map_err(|_| anyhow!("object isn't mutable"))?
+            .ok_or_else(|| anyhow!("invalid cast"))?
+            .build(context)
+    }
+}
+
+/// Built-in target type registrations performed on every new `EnvironmentContext`.
+fn default_target_type_registry() -> BTreeMap<String, BuildableTargetConstructor> {
+    let mut registry: BTreeMap<String, BuildableTargetConstructor> = BTreeMap::new();
+
+    registry.insert(
+        "FileManifest".to_string(),
+        Box::new(|v: &Value| -> Option<Box<dyn BuildableTarget>> {
+            Some(Box::new(FileManifestTarget(v.clone())))
+        }),
+    );
+    registry.insert(
+        "PythonExecutable".to_string(),
+        Box::new(|v: &Value| -> Option<Box<dyn BuildableTarget>> {
+            Some(Box::new(PythonExecutableTarget(v.clone())))
+        }),
+    );
+    registry.insert(
+        "PythonEmbeddedResources".to_string(),
+        Box::new(|v: &Value| -> Option<Box<dyn BuildableTarget>> {
+            Some(Box::new(PythonEmbeddedResourcesTarget(v.clone())))
+        }),
+    );
+
+    registry
+}
+
 /// Represents a registered target in the Starlark environment.
 #[derive(Debug, Clone)]
 pub struct Target {
@@ -37,6 +123,14 @@ pub struct Target {
     /// What calling callable returned, if it has been called.
     pub resolved_value: Option<Value>,
 
+    /// Whether this target's callable is currently being resolved.
+    ///
+    /// Set for the duration of the callable's invocation so re-entrant
+    /// resolution (e.g. via a dependency cycle that slipped past
+    /// `resolve_target_order()`) is detected as an error instead of invoking
+    /// a side-effecting target function more than once.
+    pub resolving: bool,
+
     /// The `ResolvedTarget` instance this target's build() returned.
     ///
     /// TODO consider making this an Arc<T> so we don't have to clone it.
@@ -44,7 +138,7 @@ pub struct Target {
 }
 
 /// Holds state for evaluating a Starlark config file.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct EnvironmentContext {
     pub logger: slog::Logger,
 
@@ -98,6 +192,51 @@ pub struct EnvironmentContext {
     ///
     /// This will change the default target to resolve.
     pub build_script_mode: bool,
+
+    /// Registry of Starlark value type names to constructors of `BuildableTarget`.
+    ///
+    /// This allows `build_resolved_target()` to build arbitrary Starlark
+    /// value types without hardcoding a match over known type names. New
+    /// target types can be registered via `register_target_type()`. Shared
+    /// via `Rc` so registrations survive cloning the context.
+    target_type_registry: Rc<RefCell<BTreeMap<String, BuildableTargetConstructor>>>,
+
+    /// Source map of files loaded while evaluating the Starlark config.
+    ///
+    /// Used to render evaluation errors as formatted diagnostics pointing at
+    /// the offending source, rather than bare error strings. Shared via
+    /// `Rc` so every clone of the context reports into the same map.
+    code_map: Rc<RefCell<CodeMap>>,
+
+    /// Span covering the whole of `config_path` within `code_map`.
+    ///
+    /// Errors raised by native (non-Starlark) functions don't have access to
+    /// the Starlark call stack's source locations, so they point at this
+    /// span as a best-effort location rather than emitting a bare,
+    /// unlocated diagnostic.
+    config_file_span: codemap::Span,
+}
+
+impl std::fmt::Debug for EnvironmentContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("EnvironmentContext")
+            .field("verbose", &self.verbose)
+            .field("cwd", &self.cwd)
+            .field("config_path", &self.config_path)
+            .field("build_host_triple", &self.build_host_triple)
+            .field("build_target_triple", &self.build_target_triple)
+            .field("build_release", &self.build_release)
+            .field("build_opt_level", &self.build_opt_level)
+            .field("build_path", &self.build_path)
+            .field("python_distributions_path", &self.python_distributions_path)
+            .field("targets", &self.targets)
+            .field("targets_order", &self.targets_order)
+            .field("default_target", &self.default_target)
+            .field("default_build_script_target", &self.default_build_script_target)
+            .field("resolve_targets", &self.resolve_targets)
+            .field("build_script_mode", &self.build_script_mode)
+            .finish()
+    }
 }
 
 impl EnvironmentContext {
@@ -124,8 +263,9 @@ impl EnvironmentContext {
         };
 
         let build_path = parent.join("build");
+        let code_map = Rc::new(RefCell::new(CodeMap::new()));
 
-        Ok(EnvironmentContext {
+        let mut context = EnvironmentContext {
             logger: logger.clone(),
             verbose,
             cwd: parent,
@@ -142,7 +282,94 @@ impl EnvironmentContext {
             default_build_script_target: None,
             resolve_targets,
             build_script_mode,
-        })
+            target_type_registry: Rc::new(RefCell::new(default_target_type_registry())),
+            code_map: code_map.clone(),
+            // Placeholder, overwritten immediately below once `context` exists and
+            // `register_source_file()` can be called on it.
+            config_file_span: code_map.borrow_mut().add_file(String::new(), String::new()).span,
+        };
+
+        // Best-effort: a missing config file shouldn't prevent constructing a
+        // context (e.g. tests that exercise target registration against a
+        // path that was never written to disk). The span just ends up
+        // covering an empty file in that case.
+        let config_content = std::fs::read_to_string(config_path).unwrap_or_default();
+        context.config_file_span =
+            context.register_source_file(config_path.display().to_string(), config_content);
+
+        Ok(context)
+    }
+
+    /// Register a source file's contents with the shared `CodeMap`.
+    ///
+    /// Call this once per Starlark file loaded so subsequent diagnostics can
+    /// point at the offending source. Returns the `codemap::Span` covering
+    /// the whole file.
+    ///
+    /// TODO narrow diagnostics down to the call site that raised an error by
+    /// reading a location out of the Starlark `CallStack`. Native (non-Starlark)
+    /// functions like `starlark_resolve_target` and `starlark_set_build_path`
+    /// don't do this yet -- they pass this whole-file span to `emit_diagnostic`
+    /// as-is. This is open, unimplemented work, not something resolved
+    /// elsewhere in this module.
+    pub fn register_source_file(
+        &self,
+        name: impl Into<String>,
+        content: impl Into<String>,
+    ) -> codemap::Span {
+        self.code_map.borrow_mut().add_file(name.into(), content.into()).span
+    }
+
+    /// Render a diagnostic to stderr and return an error summarizing it.
+    ///
+    /// `span` should be the Starlark source span of the offending construct
+    /// (e.g. the `resolve_target()` call site), if known. In practice, current
+    /// callers pass either `EnvironmentContext::config_file_span` -- the whole
+    /// config file -- or `None` (as `build_resolved_target()` does), never a
+    /// real call-site span; see the TODO on `register_source_file`.
+    pub fn emit_diagnostic(&self, message: impl Into<String>, span: Option<codemap::Span>) -> anyhow::Error {
+        let message = message.into();
+
+        let spans = match span {
+            Some(span) => vec![SpanLabel {
+                span,
+                style: SpanStyle::Primary,
+                label: Some(message.clone()),
+            }],
+            None => Vec::new(),
+        };
+
+        let diagnostic = Diagnostic {
+            level: Level::Error,
+            message: message.clone(),
+            code: Some("PYOXIDIZER_BUILD".to_string()),
+            spans,
+        };
+
+        let code_map = self.code_map.borrow();
+        Emitter::stderr(ColorConfig::Auto, Some(&code_map)).emit(&[diagnostic]);
+
+        anyhow!(message)
+    }
+
+    /// Register a Starlark value type as buildable/runnable via a target.
+    ///
+    /// `type_name` is the value returned by `Value::get_type()` for values of
+    /// this type. `constructor` is called with the resolved target value and
+    /// should return `Some(Box<dyn BuildableTarget>)` if it can handle the
+    /// value, `None` otherwise.
+    ///
+    /// This allows downstream consumers of this module to plug in their own
+    /// Starlark value types as targets without modifying
+    /// `build_resolved_target()`.
+    pub fn register_target_type(
+        &mut self,
+        type_name: impl Into<String>,
+        constructor: BuildableTargetConstructor,
+    ) {
+        self.target_type_registry
+            .borrow_mut()
+            .insert(type_name.into(), constructor);
     }
 
     pub fn set_build_path(&mut self, path: &Path) -> Result<()> {
@@ -179,6 +406,7 @@ impl EnvironmentContext {
                 callable,
                 depends,
                 resolved_value: None,
+                resolving: false,
                 built_target: None,
             },
         );
@@ -208,6 +436,78 @@ impl EnvironmentContext {
         }
     }
 
+    /// Compute a deterministic, dependency-respecting resolution order.
+    ///
+    /// Builds a dependency graph over all registered targets (an edge from a
+    /// target to each of its `depends`), validates that every depended-upon
+    /// name refers to a registered target, and returns a topological
+    /// ordering restricted to `roots` and their transitive dependencies.
+    ///
+    /// Returns a descriptive error naming the offending targets if the
+    /// dependency graph contains a cycle or an unresolvable dependency name.
+    pub fn resolve_target_order(&self, roots: &[String]) -> Result<Vec<String>> {
+        let mut graph = DiGraph::<String, ()>::new();
+        let mut nodes: BTreeMap<String, NodeIndex> = BTreeMap::new();
+
+        for name in self.targets.keys() {
+            let index = graph.add_node(name.clone());
+            nodes.insert(name.clone(), index);
+        }
+
+        for (name, target) in &self.targets {
+            let from = nodes[name];
+
+            for depend in &target.depends {
+                let to = *nodes.get(depend).ok_or_else(|| {
+                    anyhow!("target {} depends on unknown target {}", name, depend)
+                })?;
+
+                graph.add_edge(from, to, ());
+            }
+        }
+
+        let order = toposort(&graph, None).map_err(|cycle| {
+            let members = petgraph::algo::tarjan_scc(&graph)
+                .into_iter()
+                .find(|component| component.contains(&cycle.node_id()))
+                .unwrap_or_else(|| vec![cycle.node_id()]);
+            let names = members
+                .iter()
+                .map(|index| graph[*index].as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            anyhow!(
+                "cycle detected in target dependencies involving targets {}",
+                names
+            )
+        })?;
+
+        // `toposort` yields dependencies after their dependents because edges
+        // point from a target to its depends. Reverse so dependencies are
+        // built first, then filter down to the requested roots and whatever
+        // they transitively depend on.
+        let mut reachable = HashSet::new();
+        let mut stack: Vec<String> = roots.to_vec();
+
+        while let Some(name) = stack.pop() {
+            if !self.targets.contains_key(&name) {
+                return Err(anyhow!("target {} is not registered", name));
+            }
+
+            if reachable.insert(name.clone()) {
+                stack.extend(self.targets[&name].depends.iter().cloned());
+            }
+        }
+
+        Ok(order
+            .into_iter()
+            .rev()
+            .map(|index| graph[index].clone())
+            .filter(|name| reachable.contains(name))
+            .collect())
+    }
+
     /// Build a resolved target.
     pub fn build_resolved_target(&mut self, target: &str) -> Result<ResolvedTarget> {
         let resolved_value = if let Some(t) = self.targets.get(target) {
@@ -218,10 +518,10 @@ impl EnvironmentContext {
             if let Some(v) = &t.resolved_value {
                 v.clone()
             } else {
-                return Err(anyhow!("target {} is not resolved", target));
+                return Err(self.emit_diagnostic(format!("target {} is not resolved", target), None));
             }
         } else {
-            return Err(anyhow!("target {} is not registered", target));
+            return Err(self.emit_diagnostic(format!("target {} is not registered", target), None));
         };
 
         let output_path = self
@@ -245,25 +545,17 @@ impl EnvironmentContext {
             output_path,
         };
 
-        // TODO surely this can use dynamic dispatch.
-        let resolved_target: ResolvedTarget = match resolved_value.get_type() {
-            "FileManifest" => resolved_value
-                .downcast_mut::<FileManifest>()
-                .map_err(|_| anyhow!("object isn't mutable"))?
-                .ok_or_else(|| anyhow!("invalid cast"))?
-                .build(&context),
-            "PythonExecutable" => resolved_value
-                .downcast_mut::<PythonExecutable>()
-                .map_err(|_| anyhow!("object isn't mutable"))?
-                .ok_or_else(|| anyhow!("invalid cast"))?
-                .build(&context),
-            "PythonEmbeddedResources" => resolved_value
-                .downcast_mut::<PythonEmbeddedResources>()
-                .map_err(|_| anyhow!("object isn't mutable"))?
-                .ok_or_else(|| anyhow!("invalid cast"))?
-                .build(&context),
-            _ => Err(anyhow!("could not determine type of target")),
-        }?;
+        let mut buildable = {
+            let registry = self.target_type_registry.borrow();
+            let constructor = registry.get(resolved_value.get_type());
+
+            match constructor.and_then(|constructor| constructor(&resolved_value)) {
+                Some(buildable) => buildable,
+                None => return Err(self.emit_diagnostic("could not determine type of target", None)),
+            }
+        };
+
+        let resolved_target: ResolvedTarget = buildable.build(&context)?;
 
         self.targets.get_mut(target).unwrap().built_target = Some(resolved_target.clone());
 
@@ -305,6 +597,99 @@ impl EnvironmentContext {
     }
 }
 
+/// Fluent builder for `EnvironmentContext`.
+///
+/// `EnvironmentContext::new()` takes a large number of positional arguments,
+/// most of which have a sensible default. This builder is the ergonomic
+/// entry point for embedders: it defaults the host/target triple to the
+/// detected host and release/opt-level/verbose to sane values, and can grow
+/// new optional settings without breaking existing callers.
+pub struct EnvironmentContextBuilder {
+    logger: slog::Logger,
+    verbose: bool,
+    config_path: PathBuf,
+    build_host_triple: String,
+    build_target_triple: String,
+    build_release: bool,
+    build_opt_level: String,
+    resolve_targets: Option<Vec<String>>,
+    build_script_mode: bool,
+}
+
+impl EnvironmentContextBuilder {
+    /// Construct a new builder for the given logger and config file path.
+    ///
+    /// The host and target triple both default to the triple this binary was
+    /// built for.
+    pub fn new(logger: &slog::Logger, config_path: impl AsRef<Path>) -> Self {
+        EnvironmentContextBuilder {
+            logger: logger.clone(),
+            verbose: false,
+            config_path: config_path.as_ref().to_path_buf(),
+            build_host_triple: crate::project_building::HOST.to_string(),
+            build_target_triple: crate::project_building::HOST.to_string(),
+            build_release: false,
+            build_opt_level: "0".to_string(),
+            resolve_targets: None,
+            build_script_mode: false,
+        }
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn config_path(mut self, config_path: impl AsRef<Path>) -> Self {
+        self.config_path = config_path.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn build_host_triple(mut self, triple: impl Into<String>) -> Self {
+        self.build_host_triple = triple.into();
+        self
+    }
+
+    pub fn build_target_triple(mut self, triple: impl Into<String>) -> Self {
+        self.build_target_triple = triple.into();
+        self
+    }
+
+    pub fn release(mut self, release: bool) -> Self {
+        self.build_release = release;
+        self
+    }
+
+    pub fn build_opt_level(mut self, opt_level: impl Into<String>) -> Self {
+        self.build_opt_level = opt_level.into();
+        self
+    }
+
+    pub fn resolve_targets(mut self, targets: Option<Vec<String>>) -> Self {
+        self.resolve_targets = targets;
+        self
+    }
+
+    pub fn build_script_mode(mut self, build_script_mode: bool) -> Self {
+        self.build_script_mode = build_script_mode;
+        self
+    }
+
+    pub fn build(self) -> Result<EnvironmentContext> {
+        EnvironmentContext::new(
+            &self.logger,
+            self.verbose,
+            &self.config_path,
+            &self.build_host_triple,
+            &self.build_target_triple,
+            self.build_release,
+            &self.build_opt_level,
+            self.resolve_targets,
+            self.build_script_mode,
+        )
+    }
+}
+
 impl TypedValue for EnvironmentContext {
     type Holder = Mutable<EnvironmentContext>;
     const TYPE: &'static str = "EnvironmentContext";
@@ -425,38 +810,81 @@ fn starlark_resolve_target(
 
     let target_entry = match &context.targets.get(&target) {
         Some(v) => Ok((*v).clone()),
-        None => Err(ValueError::from(RuntimeError {
-            code: "PYOXIDIZER_BUILD",
-            message: format!("target {} does not exist", target),
-            label: "resolve_target()".to_string(),
-        })),
+        None => {
+            // TODO derive this from `call_stack`'s top frame instead of the
+            // whole-file span; see the TODO on `register_source_file`.
+            let span = context.config_file_span;
+            let message = format!("target {} does not exist", target);
+            context.emit_diagnostic(message.clone(), Some(span));
+
+            Err(ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message,
+                label: "resolve_target()".to_string(),
+            }))
+        }
     }?;
 
-    // Resolve target dependencies.
-    let mut args = Vec::new();
+    if target_entry.resolving {
+        return Err(ValueError::from(RuntimeError {
+            code: "PYOXIDIZER_BUILD",
+            message: format!(
+                "target {} is already being resolved (circular dependency?)",
+                target
+            ),
+            label: "resolve_target()".to_string(),
+        }));
+    }
 
-    for depend_target in target_entry.depends {
-        let depend_target = Value::new(depend_target);
-        args.push(starlark_resolve_target(env, call_stack, &depend_target)?);
+    if let Some(target_entry) = context.targets.get_mut(&target) {
+        target_entry.resolving = true;
     }
 
-    let res = target_entry.callable.call(
-        call_stack,
-        env.clone(),
-        args,
-        LinkedHashMap::new(),
-        None,
-        None,
-    )?;
+    // From here on, `resolving` must be cleared before returning, successfully or
+    // not: leaving it set on an error path would permanently wedge this target,
+    // since every later resolve attempt would see `resolving` still `true` and
+    // report a phantom circular dependency.
+    let result = (move || -> ValueResult {
+        // Resolve target dependencies.
+        let mut args = Vec::new();
+
+        for depend_target in target_entry.depends {
+            let depend_target = Value::new(depend_target);
+            args.push(starlark_resolve_target(env, call_stack, &depend_target)?);
+        }
 
-    // TODO consider replacing the target's callable with a new function that returns the
-    // resolved value. This will ensure a target function is only ever called once.
+        target_entry.callable.call(
+            call_stack,
+            env.clone(),
+            args,
+            LinkedHashMap::new(),
+            None,
+            None,
+        )
+    })();
+
+    match result {
+        Ok(res) => {
+            // Once a call succeeds, the target's function is not run again:
+            // `resolved_value` is checked (and returned early) above before the
+            // callable is ever invoked again, and `resolving` guards against
+            // re-entrant invocation while this call is still in flight. A failed
+            // call leaves `resolved_value` unset, so a later resolve runs it again.
+            if let Some(target_entry) = context.targets.get_mut(&target) {
+                target_entry.resolving = false;
+                target_entry.resolved_value = Some(res.clone());
+            }
 
-    if let Some(target_entry) = context.targets.get_mut(&target) {
-        target_entry.resolved_value = Some(res.clone());
-    }
+            Ok(res)
+        }
+        Err(e) => {
+            if let Some(target_entry) = context.targets.get_mut(&target) {
+                target_entry.resolving = false;
+            }
 
-    Ok(res)
+            Err(e)
+        }
+    }
 }
 
 /// resolve_targets()
@@ -478,7 +906,18 @@ fn starlark_resolve_targets(env: &Environment, call_stack: &CallStack) -> ValueR
         .downcast_ref::<EnvironmentContext>()
         .ok_or(ValueError::IncorrectParameterType)?;
 
-    let targets = context.targets_to_resolve();
+    let roots = context.targets_to_resolve();
+
+    // Validate the dependency graph and compute a deterministic build order
+    // up front, rather than discovering cycles or unknown dependencies deep
+    // in recursive `resolve_target()` calls.
+    let targets = context.resolve_target_order(&roots).map_err(|e| {
+        ValueError::from(RuntimeError {
+            code: "PYOXIDIZER_BUILD",
+            message: e.to_string(),
+            label: "resolve_targets()".to_string(),
+        })
+    })?;
 
     println!("resolving {} targets", targets.len());
     for target in targets {
@@ -504,10 +943,17 @@ fn starlark_set_build_path(env: &Environment, path: &Value) -> ValueResult {
         .downcast_mut::<EnvironmentContext>()?
         .ok_or(ValueError::IncorrectParameterType)?;
 
+    // TODO derive this from `call_stack`'s top frame instead of the whole-file
+    // span; see the TODO on `register_source_file`.
+    let span = context.config_file_span;
+
     context.set_build_path(&PathBuf::from(&path)).map_err(|e| {
+        let message = e.to_string();
+        context.emit_diagnostic(message.clone(), Some(span));
+
         ValueError::from(RuntimeError {
             code: "PYOXIDIZER_BUILD",
-            message: e.to_string(),
+            message,
             label: "set_build_path()".to_string(),
         })
     })?;
@@ -657,4 +1103,249 @@ pub mod tests {
             &vec!["foo".to_string()],
         );
     }
+
+    #[test]
+    fn test_resolve_target_clears_resolving_flag_after_failure() {
+        let mut env = starlark_env();
+        starlark_eval_in_env(&mut env, "def broken():\n    fail('boom')\n").unwrap();
+        starlark_eval_in_env(&mut env, "register_target('broken', broken)").unwrap();
+
+        assert!(starlark_eval_in_env(&mut env, "resolve_target('broken')").is_err());
+
+        // A failed resolution must leave `resolving` reset; otherwise every later
+        // attempt incorrectly reports a circular dependency instead of retrying.
+        let raw_context = get_context(&env).unwrap();
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)
+            .unwrap();
+        assert!(!context.targets.get("broken").unwrap().resolving);
+        drop(context);
+        drop(raw_context);
+
+        match starlark_eval_in_env(&mut env, "resolve_target('broken')") {
+            Err(e) => assert!(!format!("{:?}", e).contains("already being resolved")),
+            Ok(_) => panic!("expected resolve_target('broken') to fail again"),
+        }
+    }
+
+    #[test]
+    fn test_resolve_unknown_target_errors_and_keeps_config_span() {
+        // This checks that `resolve_target()` on an unknown target fails with a
+        // "does not exist" error and leaves `config_file_span` (the whole config
+        // file, which its diagnostic points at) in place. The rendered diagnostic
+        // goes to stderr and isn't inspected here, and call-site precision isn't
+        // asserted either: neither
+        // `starlark_resolve_target` nor `starlark_set_build_path` currently derive
+        // one from `call_stack`, so there isn't a narrower span to assert against yet.
+        let mut env = starlark_env();
+
+        let raw_context = get_context(&env).unwrap();
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)
+            .unwrap();
+
+        // `new()` registers the config file with the code map up front, so there's
+        // always a real span to point diagnostics at, even before any target is
+        // registered.
+        let span = context.config_file_span;
+        drop(context);
+        drop(raw_context);
+
+        match starlark_eval_in_env(&mut env, "resolve_target('missing')") {
+            Err(e) => assert!(format!("{:?}", e).contains("does not exist")),
+            Ok(_) => panic!("expected resolve_target('missing') to fail"),
+        }
+
+        let raw_context = get_context(&env).unwrap();
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)
+            .unwrap();
+        assert_eq!(context.config_file_span, span);
+    }
+
+    #[test]
+    fn test_resolve_target_order_linear_chain() {
+        let mut env = starlark_env();
+        starlark_eval_in_env(&mut env, "def noop(): pass").unwrap();
+        starlark_eval_in_env(&mut env, "register_target('c', noop)").unwrap();
+        starlark_eval_in_env(&mut env, "register_target('b', noop, depends=['c'])").unwrap();
+        starlark_eval_in_env(&mut env, "register_target('a', noop, depends=['b'])").unwrap();
+
+        let raw_context = get_context(&env).unwrap();
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)
+            .unwrap();
+
+        let order = context.resolve_target_order(&["a".to_string()]).unwrap();
+        assert_eq!(
+            order,
+            vec!["c".to_string(), "b".to_string(), "a".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_resolve_target_order_cycle_detected() {
+        let mut env = starlark_env();
+        starlark_eval_in_env(&mut env, "def noop(): pass").unwrap();
+        starlark_eval_in_env(&mut env, "register_target('a', noop, depends=['b'])").unwrap();
+        starlark_eval_in_env(&mut env, "register_target('b', noop, depends=['a'])").unwrap();
+
+        let raw_context = get_context(&env).unwrap();
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)
+            .unwrap();
+
+        let err = context.resolve_target_order(&["a".to_string()]).unwrap_err();
+        assert!(
+            err.to_string().contains("cycle detected in target dependencies"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_resolve_target_order_unknown_dependency() {
+        let mut env = starlark_env();
+        starlark_eval_in_env(&mut env, "def noop(): pass").unwrap();
+        starlark_eval_in_env(&mut env, "register_target('a', noop, depends=['ghost'])").unwrap();
+
+        let raw_context = get_context(&env).unwrap();
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)
+            .unwrap();
+
+        let err = context.resolve_target_order(&["a".to_string()]).unwrap_err();
+        assert!(
+            err.to_string().contains("depends on unknown target ghost"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_resolve_target_order_multi_root_excludes_unreachable() {
+        let mut env = starlark_env();
+        starlark_eval_in_env(&mut env, "def noop(): pass").unwrap();
+        starlark_eval_in_env(&mut env, "register_target('b', noop)").unwrap();
+        starlark_eval_in_env(&mut env, "register_target('a', noop, depends=['b'])").unwrap();
+        starlark_eval_in_env(&mut env, "register_target('e', noop)").unwrap();
+        starlark_eval_in_env(&mut env, "register_target('d', noop, depends=['e'])").unwrap();
+        starlark_eval_in_env(&mut env, "register_target('unrelated', noop)").unwrap();
+
+        let raw_context = get_context(&env).unwrap();
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)
+            .unwrap();
+
+        let order = context
+            .resolve_target_order(&["a".to_string(), "d".to_string()])
+            .unwrap();
+
+        assert_eq!(order.len(), 4);
+        assert!(!order.contains(&"unrelated".to_string()));
+        assert!(
+            order.iter().position(|t| t == "b").unwrap()
+                < order.iter().position(|t| t == "a").unwrap()
+        );
+        assert!(
+            order.iter().position(|t| t == "e").unwrap()
+                < order.iter().position(|t| t == "d").unwrap()
+        );
+    }
+
+    /// A minimal Starlark value type, standing in for an embedder's own custom
+    /// target type in `test_build_resolved_target_uses_registered_custom_type`.
+    struct CustomTargetMarker;
+
+    impl TypedValue for CustomTargetMarker {
+        type Holder = Mutable<CustomTargetMarker>;
+        const TYPE: &'static str = "CustomTargetMarker";
+
+        fn values_for_descendant_check_and_freeze(&self) -> Box<dyn Iterator<Item = Value>> {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    struct CustomBuildableTarget;
+
+    impl BuildableTarget for CustomBuildableTarget {
+        fn build(&mut self, _context: &BuildContext) -> Result<ResolvedTarget> {
+            // The call reaching here, rather than the "could not determine type
+            // of target" error `build_resolved_target` raises when no
+            // constructor matches, is what proves the registry dispatched to
+            // our constructor.
+            Err(anyhow!("custom target type built"))
+        }
+    }
+
+    #[test]
+    fn test_build_resolved_target_uses_registered_custom_type() -> Result<(), ValueError> {
+        let mut env = starlark_env();
+        starlark_eval_in_env(&mut env, "def noop(): pass").unwrap();
+        starlark_eval_in_env(&mut env, "register_target('custom', noop)").unwrap();
+
+        let raw_context = get_context(&env).unwrap();
+        let mut context = raw_context
+            .downcast_mut::<EnvironmentContext>()?
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        context.register_target_type(
+            CustomTargetMarker::TYPE,
+            Box::new(|_v: &Value| -> Option<Box<dyn BuildableTarget>> {
+                Some(Box::new(CustomBuildableTarget))
+            }),
+        );
+
+        context.targets.get_mut("custom").unwrap().resolved_value =
+            Some(Value::new(CustomTargetMarker));
+
+        drop(context);
+        drop(raw_context);
+
+        let raw_context = get_context(&env).unwrap();
+        let mut context = raw_context
+            .downcast_mut::<EnvironmentContext>()?
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let err = context.build_resolved_target("custom").unwrap_err();
+        assert_eq!(err.to_string(), "custom target type built");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_environment_context_builder_defaults_and_setters() -> Result<()> {
+        let logger = slog::Logger::root(slog::Discard, slog::o!());
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let config_path = td.path().join("pyoxidizer.bzl");
+
+        let context = EnvironmentContextBuilder::new(&logger, &config_path)
+            .verbose(true)
+            .build_host_triple("aarch64-apple-darwin")
+            .build_target_triple("x86_64-unknown-linux-gnu")
+            .release(true)
+            .build_opt_level("2")
+            .resolve_targets(Some(vec!["default".to_string()]))
+            .build_script_mode(true)
+            .build()?;
+
+        assert!(context.verbose);
+        assert_eq!(context.config_path, config_path);
+        assert_eq!(context.build_host_triple, "aarch64-apple-darwin");
+        assert_eq!(context.build_target_triple, "x86_64-unknown-linux-gnu");
+        assert!(context.build_release);
+        assert_eq!(context.build_opt_level, "2");
+        assert_eq!(context.resolve_targets, Some(vec!["default".to_string()]));
+        assert!(context.build_script_mode);
+        assert_eq!(context.build_path, td.path().join("build"));
+
+        Ok(())
+    }
 }